@@ -1,10 +1,24 @@
-use clap::Parser;
-use log::info;
+use clap::{Parser, ValueEnum};
+use log::{error, info};
+use std::time::Duration;
 use wasymex::{
-    checks::{DivisionByZeroCheck, MemoryCheck},
+    budget::Budget,
+    checks::{
+        DivisionByZeroCheck, InvalidConversionCheck, MemoryCheck, MemoryGrowCheck, UnreachableCheck,
+    },
     engine::Engine,
+    host::WasiConfig,
+    search::{BreadthFirstSearch, CoverageSearch, DepthFirstSearch, RandomPathSearch},
 };
 
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Search {
+    Bfs,
+    Dfs,
+    RandomPath,
+    Coverage,
+}
+
 #[derive(Parser, Debug)]
 struct Args {
     #[arg(short, long)]
@@ -16,8 +30,76 @@ struct Args {
     #[arg(long)]
     max_hotness: Option<usize>,
 
+    /// Order in which pending paths are explored
+    #[arg(long, value_enum, default_value_t = Search::Bfs)]
+    search: Search,
+
+    /// Merge paths that meet at the same location when each merged term stays within this many nodes
+    #[arg(long)]
+    merge: Option<usize>,
+
+    /// Seed for the random-path search
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Wall-clock seconds allowed per analyzed function
+    #[arg(long)]
+    timeout: Option<u64>,
+
+    /// Wall-clock seconds allowed for the whole run
+    #[arg(long)]
+    total_timeout: Option<u64>,
+
+    /// Maximum number of pending paths
+    #[arg(long)]
+    max_executions: Option<usize>,
+
+    /// Maximum number of finished paths per analyzed function
+    #[arg(long)]
+    max_paths: Option<usize>,
+
+    /// Maximum number of solver queries for the whole run
+    #[arg(long)]
+    max_queries: Option<usize>,
+
+    /// Milliseconds after which a single solver query gives up
+    #[arg(long)]
+    query_timeout: Option<u64>,
+
+    /// Treat imported and mutable exported globals as symbolic inputs
+    #[arg(long)]
+    symbolic_globals: bool,
+
     #[arg(short, long)]
     main: Option<String>,
+
+    /// Analyze from an entry point after instantiating the module: the given export, `_start`, or the start function
+    #[arg(short, long, num_args = 0..=1, default_missing_value = "")]
+    entry: Option<String>,
+
+    /// Model WASI imports
+    #[arg(long)]
+    wasi: bool,
+
+    /// Number of symbolic bytes available on stdin
+    #[arg(long, default_value_t = 0)]
+    stdin_size: usize,
+
+    /// Command-line argument passed to the program, may be repeated
+    #[arg(long = "arg")]
+    args: Vec<String>,
+
+    /// Length of a symbolic command-line argument, passed after the `--arg` ones, may be repeated
+    #[arg(long = "symbolic-arg")]
+    symbolic_args: Vec<usize>,
+
+    /// Environment variable passed to the program as `KEY=VALUE`, may be repeated
+    #[arg(long = "env")]
+    env: Vec<String>,
+
+    /// Length of a symbolic `KEY=VALUE` environment entry, passed after the `--env` ones, may be repeated
+    #[arg(long = "symbolic-env")]
+    symbolic_env: Vec<usize>,
 }
 
 fn analyze_module(engine: &mut Engine) {
@@ -82,9 +164,93 @@ fn main() {
     if let Some(max_loop_iters) = args.max_hotness {
         engine.set_max_hotness(max_loop_iters);
     }
+    engine.set_symbolic_globals(args.symbolic_globals);
+    engine.set_merging(args.merge);
+    engine.set_budget(Budget {
+        func_time: args.timeout.map(Duration::from_secs),
+        total_time: args.total_timeout.map(Duration::from_secs),
+        max_live_executions: args.max_executions,
+        max_paths: args.max_paths,
+        max_queries: args.max_queries,
+        query_timeout: args.query_timeout.map(Duration::from_millis),
+    });
+    match args.search {
+        Search::Bfs => engine.set_search_strategy(Box::new(BreadthFirstSearch::new())),
+        Search::Dfs => engine.set_search_strategy(Box::new(DepthFirstSearch::new())),
+        Search::RandomPath => {
+            engine.set_search_strategy(Box::new(RandomPathSearch::new(args.seed)))
+        }
+        Search::Coverage => engine.set_search_strategy(Box::new(CoverageSearch::new())),
+    }
+
+    if args.wasi {
+        let mut config = WasiConfig::new();
+        config.stdin = WasiConfig::symbolic_bytes(&context, "stdin", args.stdin_size);
+        config.args = args
+            .args
+            .iter()
+            .map(|arg| WasiConfig::concrete_bytes(&context, arg.as_bytes()))
+            .collect();
+        for len in &args.symbolic_args {
+            let name = format!("argv{}", config.args.len());
+            config
+                .args
+                .push(WasiConfig::symbolic_bytes(&context, &name, *len));
+        }
+        config.env = args
+            .env
+            .iter()
+            .map(|var| WasiConfig::concrete_bytes(&context, var.as_bytes()))
+            .collect();
+        for len in &args.symbolic_env {
+            let name = format!("environ{}", config.env.len());
+            config
+                .env
+                .push(WasiConfig::symbolic_bytes(&context, &name, *len));
+        }
+        engine.add_wasi_models(config);
+    }
 
     engine.add_check(Box::new(DivisionByZeroCheck::new()));
     engine.add_check(Box::new(MemoryCheck::new()));
+    engine.add_check(Box::new(InvalidConversionCheck::new()));
+    engine.add_check(Box::new(MemoryGrowCheck::new()));
+    engine.add_check(Box::new(UnreachableCheck::new()));
+
+    if let Some(entry) = args.entry {
+        let export = if entry.is_empty() { "_start" } else { &entry };
+        let exported = context.module.exports.iter().find_map(|e| match e.item {
+            walrus::ExportItem::Function(id) if e.name == export => Some((id, export.to_string())),
+            _ => None,
+        });
+        let start = match context.module.start {
+            Some(id) if entry.is_empty() => Some((id, String::from("start"))),
+            _ => None,
+        };
+        let (func_id, name) = match exported.or(start) {
+            Some(entry) => entry,
+            None => {
+                error!("No entry point {} found", export);
+                return;
+            }
+        };
+        let func = context.module.funcs.get(func_id);
+        let local_func = match wasymex::engine::as_local_func(func) {
+            Some(local_func) => local_func,
+            None => {
+                error!("Entry point {} is imported", name);
+                return;
+            }
+        };
+        if let Some(start_id) = context.module.start {
+            if wasymex::engine::as_local_func(context.module.funcs.get(start_id)).is_none() {
+                error!("Start function of the module is imported");
+                return;
+            }
+        }
+        engine.analyze_entry(local_func, func_id, &name);
+        return;
+    }
 
     match args.main {
         None => analyze_module(&mut engine),
@@ -97,8 +263,10 @@ fn main() {
                 }
             };
             let func = context.module.funcs.get(func_id);
-            let local_func = wasymex::engine::as_local_func(func).unwrap();
-            engine.analyze_func(local_func, func_id, &main)
+            match wasymex::engine::as_local_func(func) {
+                Some(local_func) => engine.analyze_func(local_func, func_id, &main),
+                None => error!("Function {} is imported", main),
+            }
         }
     }
 }
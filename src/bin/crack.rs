@@ -39,7 +39,15 @@ fn main() {
     let local_func = wasymex::engine::as_local_func(func).unwrap();
 
     let mut initial = engine.get_initial_execution(local_func, func_id);
-    let memory = initial.state.memory.as_mut().unwrap();
+    // The password goes in memory 0, the module's first memory
+    let memory_id = context
+        .module
+        .memories
+        .iter()
+        .next()
+        .expect("module has no memory")
+        .id();
+    let memory = initial.state.memories.get_mut(&memory_id).unwrap();
 
     let password_len = 5;
     engine.set_max_hotness(password_len + 2);
@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+/// Limits on how much work an analysis may do, where `None` means unlimited
+#[derive(Debug, Clone, Default)]
+pub struct Budget {
+    pub func_time: Option<Duration>, // wall-clock time per analyzed function
+    pub total_time: Option<Duration>, // wall-clock time for the whole run
+    pub max_live_executions: Option<usize>,
+    pub max_paths: Option<usize>, // finished paths per analyzed function
+    pub max_queries: Option<usize>, // solver queries for the whole run
+    pub query_timeout: Option<Duration>,
+}
+
+impl Budget {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// The budget that ran out
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    Time,
+    LiveExecutions,
+    Paths,
+    Queries,
+}
@@ -0,0 +1,70 @@
+use crate::checks::{run_constraints, Check, CheckResult};
+use crate::context::{Context, Truncation};
+use crate::state::Execution;
+use crate::value::{SymVal, Val};
+use std::collections::HashMap;
+use walrus::ir;
+
+#[derive(Clone, Debug)]
+pub struct InvalidConversionCheck<'ctx> {
+    constraints: HashMap<u32, z3::ast::Bool<'ctx>>,
+}
+
+impl<'ctx> InvalidConversionCheck<'ctx> {
+    pub fn new() -> Self {
+        InvalidConversionCheck {
+            constraints: HashMap::new(),
+        }
+    }
+}
+
+impl<'ctx> Check<'ctx> for InvalidConversionCheck<'ctx> {
+    fn name(&self) -> &'static str {
+        "InvalidConversion"
+    }
+
+    fn check(
+        &mut self,
+        context: &'ctx Context,
+        execution: &Execution<'ctx>,
+        instr: &ir::Instr,
+        loc: &ir::InstrLocId,
+    ) {
+        if let ir::Instr::Unop(imm) = instr {
+            let truncation = match Truncation::from_op(imm.op) {
+                Some(truncation) if !truncation.saturating => truncation,
+                _ => return,
+            };
+
+            let frame = execution.state.call_stack.last().unwrap();
+            let operand = frame.value_stack.last().unwrap().as_sym(&context.context);
+            match operand {
+                SymVal::F32(val) | SymVal::F64(val) => {
+                    self.constraints.insert(loc.data(), truncation.traps(&val));
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    fn run(
+        &mut self,
+        context: &'ctx Context,
+        execution: &Execution<'ctx>,
+        inputs: &HashMap<ir::LocalId, Val<'ctx>>,
+        globals: &HashMap<walrus::GlobalId, Val<'ctx>>,
+    ) -> CheckResult {
+        run_constraints(
+            context,
+            execution,
+            inputs,
+            globals,
+            &self.constraints,
+            "invalid conversion to integer",
+        )
+    }
+
+    fn constraints_mut(&mut self) -> &mut HashMap<u32, z3::ast::Bool<'ctx>> {
+        &mut self.constraints
+    }
+}
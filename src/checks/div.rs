@@ -1,12 +1,12 @@
-use crate::checks::{Check, CheckResult};
+use crate::checks::{run_constraints, Check, CheckResult};
 use crate::context::Context;
-use crate::reporter::Reporter;
 use crate::state::Execution;
 use crate::value::{SymVal, Val};
 use std::collections::HashMap;
 use walrus::ir;
-use z3::ast::Ast;
+use z3::ast::{Ast, BV};
 
+/// Flags integer divisions and remainders that trap, by a zero divisor or a signed quotient that overflows
 #[derive(Clone, Debug)]
 pub struct DivisionByZeroCheck<'ctx> {
     constraints: HashMap<u32, z3::ast::Bool<'ctx>>,
@@ -40,18 +40,31 @@ impl<'ctx> Check<'ctx> for DivisionByZeroCheck<'ctx> {
                     | ir::BinaryOp::I32DivU
                     | ir::BinaryOp::I32RemS
                     | ir::BinaryOp::I32RemU
+                    | ir::BinaryOp::I64DivS
+                    | ir::BinaryOp::I64DivU
+                    | ir::BinaryOp::I64RemS
+                    | ir::BinaryOp::I64RemU
             ) {
-                let rhs = &frame.value_stack[frame.value_stack.len() - 1];
-                match rhs {
-                    Val::Sym(val) => {
-                        self.constraints
-                            .insert(loc.data(), val.as_i32()._eq(&context.zero(32)));
-                    }
-                    Val::Conc(val) => {
-                        let val = SymVal::from_concrete(&context.context, val);
-                        self.constraints
-                            .insert(loc.data(), val.as_i32()._eq(&context.zero(32)));
+                let len = frame.value_stack.len();
+                let lhs = frame.value_stack[len - 2].as_sym(&context.context);
+                let rhs = frame.value_stack[len - 1].as_sym(&context.context);
+                match (lhs, rhs) {
+                    (SymVal::I32(lhs), SymVal::I32(rhs)) | (SymVal::I64(lhs), SymVal::I64(rhs)) => {
+                        let size = rhs.get_size();
+                        let mut traps = rhs._eq(&context.zero(size));
+                        // Signed division also traps when the quotient INT_MIN / -1 doesn't fit
+                        if matches!(imm.op, ir::BinaryOp::I32DivS | ir::BinaryOp::I64DivS) {
+                            let min = BV::from_i64(&context.context, i64::MIN >> (64 - size), size);
+                            let minus_one = BV::from_i64(&context.context, -1, size);
+                            let overflows = z3::ast::Bool::and(
+                                &context.context,
+                                &[&lhs._eq(&min), &rhs._eq(&minus_one)],
+                            );
+                            traps = z3::ast::Bool::or(&context.context, &[&traps, &overflows]);
+                        }
+                        self.constraints.insert(loc.data(), traps);
                     }
+                    _ => unreachable!(),
                 }
                 // trace!("{:?}", self.constraints);
             }
@@ -63,23 +76,19 @@ impl<'ctx> Check<'ctx> for DivisionByZeroCheck<'ctx> {
         context: &'ctx Context,
         execution: &Execution<'ctx>,
         inputs: &HashMap<ir::LocalId, Val<'ctx>>,
+        globals: &HashMap<walrus::GlobalId, Val<'ctx>>,
     ) -> CheckResult {
-        let solver = execution.get_solver(context);
-        for (loc, constraint) in &self.constraints {
-            solver.push();
-            solver.assert(constraint);
-
-            if solver.check() != z3::SatResult::Unsat {
-                return CheckResult::Fail(format!(
-                    "division by zero @ +{} with inputs {}",
-                    loc,
-                    Reporter::format_model(inputs, &solver.get_model().unwrap())
-                ));
-            }
-
-            solver.pop(1);
-        }
+        run_constraints(
+            context,
+            execution,
+            inputs,
+            globals,
+            &self.constraints,
+            "division by zero or overflow",
+        )
+    }
 
-        CheckResult::Ok
+    fn constraints_mut(&mut self) -> &mut HashMap<u32, z3::ast::Bool<'ctx>> {
+        &mut self.constraints
     }
 }
@@ -0,0 +1,151 @@
+use crate::checks::{run_constraints, Check, CheckResult};
+use crate::context::Context;
+use crate::state::Execution;
+use crate::value::Val;
+use std::collections::HashMap;
+use walrus::ir;
+
+/// Flags `memory.grow` calls whose result is never compared even though the growth can fail
+///
+/// The result is followed while it is on top of the stack and through the locals it is
+/// saved to; it counts as checked once a comparison reads it and as unchecked when it is
+/// dropped, overwritten or still held at the end of the path. Any other use stops tracking it.
+#[derive(Clone, Debug)]
+pub struct MemoryGrowCheck<'ctx> {
+    constraints: HashMap<u32, z3::ast::Bool<'ctx>>,
+    /// Result on top of the stack, and whether one operand was pushed above it since
+    top: Option<(u32, z3::ast::Bool<'ctx>, bool)>,
+    /// Results saved to a local, by call depth and local
+    held: HashMap<(usize, ir::LocalId), (u32, z3::ast::Bool<'ctx>)>,
+}
+
+impl<'ctx> MemoryGrowCheck<'ctx> {
+    pub fn new() -> Self {
+        MemoryGrowCheck {
+            constraints: HashMap::new(),
+            top: None,
+            held: HashMap::new(),
+        }
+    }
+
+    /// Stops tracking a result in every local that holds it
+    fn forget(&mut self, grow_loc: u32) {
+        self.held.retain(|_, (loc, _)| *loc != grow_loc);
+    }
+
+    /// Whether any local still holds a result
+    fn is_held(&self, grow_loc: u32) -> bool {
+        self.held.values().any(|(loc, _)| *loc == grow_loc)
+    }
+}
+
+fn is_comparison(instr: &ir::Instr) -> bool {
+    use ir::BinaryOp::*;
+    match instr {
+        ir::Instr::Unop(unop) => matches!(unop.op, ir::UnaryOp::I32Eqz),
+        ir::Instr::Binop(binop) => matches!(
+            binop.op,
+            I32Eq | I32Ne | I32LtS | I32LtU | I32GtS | I32GtU | I32LeS | I32LeU | I32GeS | I32GeU
+        ),
+        _ => false,
+    }
+}
+
+impl<'ctx> Check<'ctx> for MemoryGrowCheck<'ctx> {
+    fn name(&self) -> &'static str {
+        "MemoryGrow"
+    }
+
+    fn check(
+        &mut self,
+        context: &'ctx Context,
+        execution: &Execution<'ctx>,
+        instr: &ir::Instr,
+        loc: &ir::InstrLocId,
+    ) {
+        let depth = execution.state.call_stack.len();
+        let top = self.top.take();
+
+        // A local holding a result is about to be overwritten, losing it unless it is kept elsewhere
+        if let ir::Instr::LocalSet(ir::LocalSet { local })
+        | ir::Instr::LocalTee(ir::LocalTee { local }) = instr
+        {
+            if let Some((grow_loc, failed)) = self.held.remove(&(depth, *local)) {
+                let on_top = matches!(top, Some((loc, _, false)) if loc == grow_loc);
+                if !on_top && !self.is_held(grow_loc) {
+                    self.constraints.insert(grow_loc, failed);
+                }
+            }
+        }
+
+        if let Some((grow_loc, failed, under_const)) = top {
+            match instr {
+                _ if is_comparison(instr) => self.forget(grow_loc),
+                ir::Instr::Const(_) | ir::Instr::LocalGet(_) | ir::Instr::GlobalGet(_)
+                    if !under_const =>
+                {
+                    self.top = Some((grow_loc, failed, true))
+                }
+                _ if under_const => self.forget(grow_loc),
+                ir::Instr::Drop(_) => {
+                    if !self.is_held(grow_loc) {
+                        self.constraints.insert(grow_loc, failed);
+                    }
+                }
+                ir::Instr::LocalSet(set) => {
+                    self.held.insert((depth, set.local), (grow_loc, failed));
+                }
+                ir::Instr::LocalTee(tee) => {
+                    self.held
+                        .insert((depth, tee.local), (grow_loc, failed.clone()));
+                    self.top = Some((grow_loc, failed, false));
+                }
+                _ => self.forget(grow_loc),
+            }
+        }
+
+        match instr {
+            ir::Instr::MemoryGrow(imm) => {
+                let frame = execution.state.call_stack.last().unwrap();
+                let memory = &execution.state.memories[&imm.memory];
+                let num_pages = frame.value_stack.last().unwrap();
+                let failed = memory.can_grow(context, num_pages).not();
+                self.top = Some((loc.data(), failed, false));
+            }
+            ir::Instr::LocalGet(get) => {
+                if let Some((grow_loc, failed)) = self.held.get(&(depth, get.local)) {
+                    self.top = Some((*grow_loc, failed.clone(), false));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn run(
+        &mut self,
+        context: &'ctx Context,
+        execution: &Execution<'ctx>,
+        inputs: &HashMap<ir::LocalId, Val<'ctx>>,
+        globals: &HashMap<walrus::GlobalId, Val<'ctx>>,
+    ) -> CheckResult {
+        for (grow_loc, failed) in self.held.values() {
+            self.constraints.insert(*grow_loc, failed.clone());
+        }
+        run_constraints(
+            context,
+            execution,
+            inputs,
+            globals,
+            &self.constraints,
+            "unchecked memory.grow failure",
+        )
+    }
+
+    fn constraints_mut(&mut self) -> &mut HashMap<u32, z3::ast::Bool<'ctx>> {
+        &mut self.constraints
+    }
+
+    fn has_pending_state(&self) -> bool {
+        self.top.is_some() || !self.held.is_empty()
+    }
+}
@@ -1,12 +1,61 @@
-use crate::checks::{Check, CheckResult};
+use crate::checks::{run_constraints, Check, CheckResult};
 use crate::context::Context;
-use crate::memory::PAGE_SIZE;
-use crate::reporter::Reporter;
+use crate::memory::effective_address;
 use crate::state::Execution;
 use crate::value::{ConcVal, Val};
 use std::collections::HashMap;
 use walrus::ir;
 
+/// Memory, base address, static offset and length in bytes of each range `instr` touches, given the stack before it runs
+fn accesses<'a, 'ctx>(
+    instr: &ir::Instr,
+    stack: &'a [Val<'ctx>],
+) -> Vec<(walrus::MemoryId, &'a Val<'ctx>, u32, Val<'ctx>)> {
+    let operand = |depth: usize| &stack[stack.len() - depth];
+    let width = |bytes: u32| Val::Conc(ConcVal(ir::Value::I32(bytes as i32)));
+
+    match instr {
+        ir::Instr::Load(imm) => vec![(
+            imm.memory,
+            operand(1),
+            imm.arg.offset,
+            width(imm.kind.width()),
+        )],
+        ir::Instr::Store(imm) => vec![(
+            imm.memory,
+            operand(2),
+            imm.arg.offset,
+            width(imm.kind.width()),
+        )],
+        ir::Instr::AtomicRmw(imm) => vec![(
+            imm.memory,
+            operand(2),
+            imm.arg.offset,
+            width(imm.width.bytes()),
+        )],
+        ir::Instr::Cmpxchg(imm) => vec![(
+            imm.memory,
+            operand(3),
+            imm.arg.offset,
+            width(imm.width.bytes()),
+        )],
+        ir::Instr::AtomicNotify(imm) => vec![(imm.memory, operand(2), imm.arg.offset, width(4))],
+        ir::Instr::AtomicWait(imm) => vec![(
+            imm.memory,
+            operand(3),
+            imm.arg.offset,
+            width(if imm.sixty_four { 8 } else { 4 }),
+        )],
+        ir::Instr::MemoryFill(imm) => vec![(imm.memory, operand(3), 0, operand(1).clone())],
+        ir::Instr::MemoryCopy(imm) => vec![
+            (imm.dst, operand(3), 0, operand(1).clone()),
+            (imm.src, operand(2), 0, operand(1).clone()),
+        ],
+        ir::Instr::MemoryInit(imm) => vec![(imm.memory, operand(3), 0, operand(1).clone())],
+        _ => Vec::new(),
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct MemoryCheck<'ctx> {
     constraints: HashMap<u32, z3::ast::Bool<'ctx>>,
@@ -32,40 +81,19 @@ impl<'ctx> Check<'ctx> for MemoryCheck<'ctx> {
         instr: &ir::Instr,
         loc: &ir::InstrLocId,
     ) {
-        if let ir::Instr::Load(imm) = instr {
-            let frame = execution.state.call_stack.last().unwrap();
-            let memory = execution.state.memory.as_ref().unwrap();
-            let size = &memory.size;
-
-            let load_size = match imm.kind {
-                ir::LoadKind::I32 { .. } => 32,
-                ir::LoadKind::I32_8 { .. } => 8,
-                ir::LoadKind::I32_16 { .. } => 16,
-                _ => unimplemented!(),
-            };
-            let base_index = frame.value_stack.last().unwrap();
-            let end_index = context
-                .bin_op(
-                    ir::BinaryOp::I32Add,
-                    &Val::Conc(ConcVal(ir::Value::I32(load_size / 8))),
-                    &base_index,
-                )
-                .unwrap();
-
-            let byte_size = context
-                .bin_op(
-                    ir::BinaryOp::I32Mul,
-                    &Val::Conc(ConcVal(ir::Value::I32(PAGE_SIZE as i32))),
-                    size,
-                )
-                .unwrap();
+        let frame = execution.state.call_stack.last().unwrap();
+        let conditions = accesses(instr, &frame.value_stack)
+            .iter()
+            .map(|(memory, base_index, offset, len)| {
+                let address = effective_address(context, base_index, *offset);
+                execution.state.memories[memory].out_of_bounds(context, &address, len)
+            })
+            .collect::<Vec<_>>();
 
+        if !conditions.is_empty() {
             self.constraints.insert(
                 loc.data(),
-                end_index
-                    .as_sym(&context.context)
-                    .as_i32()
-                    .bvsge(byte_size.as_sym(&context.context).as_i32()),
+                z3::ast::Bool::or(&context.context, &conditions.iter().collect::<Vec<_>>()),
             );
         }
     }
@@ -75,23 +103,19 @@ impl<'ctx> Check<'ctx> for MemoryCheck<'ctx> {
         context: &'ctx Context,
         execution: &Execution<'ctx>,
         inputs: &HashMap<ir::LocalId, Val<'ctx>>,
+        globals: &HashMap<walrus::GlobalId, Val<'ctx>>,
     ) -> CheckResult {
-        let solver = execution.get_solver(context);
-        for (loc, constraint) in &self.constraints {
-            solver.push();
-            solver.assert(constraint);
-
-            if solver.check() != z3::SatResult::Unsat {
-                return CheckResult::Fail(format!(
-                    "memory out of bounds @ +{} with inputs {}",
-                    loc,
-                    Reporter::format_model(inputs, &solver.get_model().unwrap())
-                ));
-            }
-
-            solver.pop(1);
-        }
+        run_constraints(
+            context,
+            execution,
+            inputs,
+            globals,
+            &self.constraints,
+            "memory out of bounds",
+        )
+    }
 
-        CheckResult::Ok
+    fn constraints_mut(&mut self) -> &mut HashMap<u32, z3::ast::Bool<'ctx>> {
+        &mut self.constraints
     }
 }
@@ -1,15 +1,22 @@
 use crate::context::Context;
+use crate::reporter::Reporter;
 use crate::state::Execution;
 use crate::value::Val;
 use dyn_clone::DynClone;
 use std::collections::HashMap;
 use walrus::ir;
 
+mod conversion;
 mod div;
+mod grow;
 mod memory;
+mod unreachable;
 
+pub use conversion::*;
 pub use div::*;
+pub use grow::*;
 pub use memory::*;
+pub use unreachable::*;
 
 pub enum CheckResult {
     Ok,
@@ -33,7 +40,54 @@ pub trait Check<'ctx>: DynClone + std::fmt::Debug {
         context: &'ctx Context,
         execution: &Execution<'ctx>,
         inputs: &HashMap<ir::LocalId, Val<'ctx>>,
+        globals: &HashMap<walrus::GlobalId, Val<'ctx>>,
     ) -> CheckResult;
+
+    /// Conditions recorded so far by instruction location, each a finding if it can hold on the path
+    fn constraints_mut(&mut self) -> &mut HashMap<u32, z3::ast::Bool<'ctx>>;
+
+    /// Whether the check is tracking something besides its constraints, which a merge can't combine
+    fn has_pending_state(&self) -> bool {
+        false
+    }
+}
+
+/// Reports the first recorded condition that can hold on the path, as `description @ +loc`
+pub fn run_constraints<'ctx>(
+    context: &'ctx Context,
+    execution: &Execution<'ctx>,
+    inputs: &HashMap<ir::LocalId, Val<'ctx>>,
+    globals: &HashMap<walrus::GlobalId, Val<'ctx>>,
+    constraints: &HashMap<u32, z3::ast::Bool<'ctx>>,
+    description: &str,
+) -> CheckResult {
+    let solver = execution.get_solver(context);
+    for (loc, constraint) in constraints {
+        solver.push();
+        solver.assert(constraint);
+
+        match context.check_uncounted(&solver) {
+            z3::SatResult::Sat => {
+                return CheckResult::Fail(format!(
+                    "{} @ +{} with inputs {}",
+                    description,
+                    loc,
+                    Reporter::format_inputs(inputs, globals, &solver.get_model().unwrap())
+                ));
+            }
+            z3::SatResult::Unknown => {
+                return CheckResult::PossibleFail(format!(
+                    "{} @ +{} could not be ruled out",
+                    description, loc
+                ));
+            }
+            z3::SatResult::Unsat => (),
+        }
+
+        solver.pop(1);
+    }
+
+    CheckResult::Ok
 }
 
 impl<'ctx> Clone for Box<dyn Check<'ctx> + 'ctx> {
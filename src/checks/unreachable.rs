@@ -0,0 +1,60 @@
+use crate::checks::{run_constraints, Check, CheckResult};
+use crate::context::Context;
+use crate::state::Execution;
+use crate::value::Val;
+use std::collections::HashMap;
+use walrus::ir;
+
+/// Flags paths that reach an `unreachable` instruction, such as a Rust panic or a failed assertion
+#[derive(Clone, Debug)]
+pub struct UnreachableCheck<'ctx> {
+    constraints: HashMap<u32, z3::ast::Bool<'ctx>>,
+}
+
+impl<'ctx> UnreachableCheck<'ctx> {
+    pub fn new() -> Self {
+        UnreachableCheck {
+            constraints: HashMap::new(),
+        }
+    }
+}
+
+impl<'ctx> Check<'ctx> for UnreachableCheck<'ctx> {
+    fn name(&self) -> &'static str {
+        "Unreachable"
+    }
+
+    fn check(
+        &mut self,
+        context: &'ctx Context,
+        _: &Execution<'ctx>,
+        instr: &ir::Instr,
+        loc: &ir::InstrLocId,
+    ) {
+        if let ir::Instr::Unreachable(_) = instr {
+            self.constraints
+                .insert(loc.data(), z3::ast::Bool::from_bool(&context.context, true));
+        }
+    }
+
+    fn run(
+        &mut self,
+        context: &'ctx Context,
+        execution: &Execution<'ctx>,
+        inputs: &HashMap<ir::LocalId, Val<'ctx>>,
+        globals: &HashMap<walrus::GlobalId, Val<'ctx>>,
+    ) -> CheckResult {
+        run_constraints(
+            context,
+            execution,
+            inputs,
+            globals,
+            &self.constraints,
+            "unreachable reached",
+        )
+    }
+
+    fn constraints_mut(&mut self) -> &mut HashMap<u32, z3::ast::Bool<'ctx>> {
+        &mut self.constraints
+    }
+}
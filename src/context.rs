@@ -1,13 +1,156 @@
+use crate::ffi::float::{self, Rounding};
 use crate::state::TrapReason;
 use crate::value::{ConcVal, SymVal, Val};
+use std::cell::Cell;
+use std::time::Duration;
 use walrus::ir;
-use z3;
 use z3::ast::Ast;
 
+/// wasm `min`: NaN if either operand is NaN, and -0 is less than +0
+fn wasm_min(lhs: f64, rhs: f64) -> f64 {
+    if lhs.is_nan() || rhs.is_nan() {
+        f64::NAN
+    } else if lhs == rhs {
+        if lhs.is_sign_negative() {
+            lhs
+        } else {
+            rhs
+        }
+    } else {
+        lhs.min(rhs)
+    }
+}
+
+/// wasm `max`: NaN if either operand is NaN, and +0 is greater than -0
+fn wasm_max(lhs: f64, rhs: f64) -> f64 {
+    if lhs.is_nan() || rhs.is_nan() {
+        f64::NAN
+    } else if lhs == rhs {
+        if lhs.is_sign_negative() {
+            rhs
+        } else {
+            lhs
+        }
+    } else {
+        lhs.max(rhs)
+    }
+}
+
+/// A float-to-integer truncation, which traps on NaN or out-of-range operands unless it saturates
+#[derive(Debug, Clone, Copy)]
+pub struct Truncation {
+    pub signed: bool,
+    pub saturating: bool,
+    pub size: u32,
+}
+
+impl Truncation {
+    pub fn from_op(op: ir::UnaryOp) -> Option<Self> {
+        let (signed, saturating, size) = match op {
+            ir::UnaryOp::I32TruncSF32 | ir::UnaryOp::I32TruncSF64 => (true, false, 32),
+            ir::UnaryOp::I32TruncUF32 | ir::UnaryOp::I32TruncUF64 => (false, false, 32),
+            ir::UnaryOp::I64TruncSF32 | ir::UnaryOp::I64TruncSF64 => (true, false, 64),
+            ir::UnaryOp::I64TruncUF32 | ir::UnaryOp::I64TruncUF64 => (false, false, 64),
+            ir::UnaryOp::I32TruncSSatF32 | ir::UnaryOp::I32TruncSSatF64 => (true, true, 32),
+            ir::UnaryOp::I32TruncUSatF32 | ir::UnaryOp::I32TruncUSatF64 => (false, true, 32),
+            ir::UnaryOp::I64TruncSSatF32 | ir::UnaryOp::I64TruncSSatF64 => (true, true, 64),
+            ir::UnaryOp::I64TruncUSatF32 | ir::UnaryOp::I64TruncUSatF64 => (false, true, 64),
+            _ => return None,
+        };
+        Some(Truncation {
+            signed,
+            saturating,
+            size,
+        })
+    }
+
+    /// Range `[lower, upper)` of truncated values representable in the result type
+    pub fn bounds(&self) -> (f64, f64) {
+        let bits = self.size as i32;
+        if self.signed {
+            (-(2f64.powi(bits - 1)), 2f64.powi(bits - 1))
+        } else {
+            (0.0, 2f64.powi(bits))
+        }
+    }
+
+    /// Whether truncating `value` traps
+    pub fn traps<'ctx>(&self, value: &z3::ast::Float<'ctx>) -> z3::ast::Bool<'ctx> {
+        let context = value.get_ctx();
+        let (lower, upper) = self.bounds();
+        let size = float::size(value);
+        let truncated = float::round(value, Rounding::Trunc);
+        z3::ast::Bool::or(
+            context,
+            &[
+                &float::is_nan(value),
+                &float::lt(&truncated, &float::from_f64(context, lower, size)),
+                &float::ge(&truncated, &float::from_f64(context, upper, size)),
+            ],
+        )
+    }
+
+    fn conc(&self, operand: &ConcVal) -> Result<ConcVal, TrapReason> {
+        let value = match operand.0 {
+            ir::Value::F32(value) => value as f64,
+            ir::Value::F64(value) => value,
+            _ => unreachable!(),
+        };
+        if !self.saturating {
+            let (lower, upper) = self.bounds();
+            if value.is_nan() {
+                return Err(TrapReason::InvalidConversionToInteger);
+            } else if value.trunc() < lower || value.trunc() >= upper {
+                return Err(TrapReason::IntegerOverflow);
+            }
+        }
+        // Rust float-to-int casts saturate and map NaN to zero, like the saturating instructions
+        Ok(ConcVal(match (self.size, self.signed) {
+            (32, true) => ir::Value::I32(value as i32),
+            (32, false) => ir::Value::I32(value as u32 as i32),
+            (64, true) => ir::Value::I64(value as i64),
+            (64, false) => ir::Value::I64(value as u64 as i64),
+            _ => unreachable!(),
+        }))
+    }
+
+    fn sym<'ctx>(&self, operand: &SymVal<'ctx>) -> SymVal<'ctx> {
+        let value = match operand {
+            SymVal::F32(value) | SymVal::F64(value) => value,
+            _ => unreachable!(),
+        };
+        let context = value.get_ctx();
+        let mut result = float::to_int(value, self.size, self.signed);
+        if self.saturating {
+            let (lower, upper) = self.bounds();
+            let size = float::size(value);
+            let truncated = float::round(value, Rounding::Trunc);
+            let (min, max) = if self.signed {
+                (i64::MIN >> (64 - self.size), i64::MAX >> (64 - self.size))
+            } else {
+                (0, (u64::MAX >> (64 - self.size)) as i64)
+            };
+            result = float::ge(&truncated, &float::from_f64(context, upper, size))
+                .ite(&z3::ast::BV::from_i64(context, max, self.size), &result);
+            result = float::lt(&truncated, &float::from_f64(context, lower, size))
+                .ite(&z3::ast::BV::from_i64(context, min, self.size), &result);
+            result =
+                float::is_nan(value).ite(&z3::ast::BV::from_i64(context, 0, self.size), &result);
+        }
+        match self.size {
+            32 => SymVal::I32(result),
+            _ => SymVal::I64(result),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Context<'m> {
     pub context: z3::Context,
     pub module: &'m walrus::Module,
+    max_queries: Cell<Option<usize>>,
+    query_timeout: Cell<Option<Duration>>,
+    queries: Cell<usize>,
 }
 
 impl<'ctx, 'm> Context<'m> {
@@ -15,7 +158,61 @@ impl<'ctx, 'm> Context<'m> {
         let config = z3::Config::new();
         let context = z3::Context::new(&config);
 
-        Context { context, module }
+        Context {
+            context,
+            module,
+            max_queries: Cell::new(None),
+            query_timeout: Cell::new(None),
+            queries: Cell::new(0),
+        }
+    }
+
+    pub fn set_solver_budget(&self, max_queries: Option<usize>, query_timeout: Option<Duration>) {
+        self.max_queries.set(max_queries);
+        self.query_timeout.set(query_timeout);
+    }
+
+    pub fn queries_exhausted(&self) -> bool {
+        matches!(self.max_queries.get(), Some(max) if self.queries.get() >= max)
+    }
+
+    /// Creates a solver that gives up with `Unknown` once the per-query timeout passes
+    pub fn solver(&'ctx self) -> z3::Solver<'ctx> {
+        let solver = z3::Solver::new(&self.context);
+        self.set_solver_params(&solver);
+        solver
+    }
+
+    /// Independent copy of `solver` with the same assertions, for the other side of a fork
+    pub fn copy_solver(&'ctx self, solver: &z3::Solver<'ctx>) -> z3::Solver<'ctx> {
+        let copy = solver.translate(&self.context);
+        // Translating keeps the assertions but not the parameters
+        self.set_solver_params(&copy);
+        copy
+    }
+
+    fn set_solver_params(&'ctx self, solver: &z3::Solver<'ctx>) {
+        if let Some(timeout) = self.query_timeout.get() {
+            let mut params = z3::Params::new(&self.context);
+            params.set_u32("timeout", timeout.as_millis().min(u32::MAX as u128) as u32);
+            solver.set_params(&params);
+        }
+    }
+
+    /// Checks `solver` while exploring, counting the query against the budget, or returns `Unknown` once the budget is
+    /// spent
+    pub fn check(&'ctx self, solver: &z3::Solver<'ctx>) -> z3::SatResult {
+        if self.queries_exhausted() {
+            return z3::SatResult::Unknown;
+        }
+        self.queries.set(self.queries.get() + 1);
+        solver.check()
+    }
+
+    /// Checks `solver` without counting the query, so that findings are still reported once exploration runs out of
+    /// queries. Solvers from `solver` still give up after the per-query timeout.
+    pub fn check_uncounted(&'ctx self, solver: &z3::Solver<'ctx>) -> z3::SatResult {
+        solver.check()
     }
 
     pub fn zero(&'ctx self, size: u32) -> z3::ast::BV<'ctx> {
@@ -25,6 +222,54 @@ impl<'ctx, 'm> Context<'m> {
         z3::ast::BV::from_i64(&self.context, 1, size)
     }
 
+    /// Converts a Z3 boolean into the `i32` 0/1 result of a wasm comparison
+    pub fn bool_to_i32(&'ctx self, value: &z3::ast::Bool<'ctx>) -> SymVal<'ctx> {
+        SymVal::I32(value.ite(&self.one(32), &self.zero(32)))
+    }
+
+    /// Reduces a shift count modulo the operand width, as wasm shifts do
+    fn shift_count(&'ctx self, count: &z3::ast::BV<'ctx>) -> z3::ast::BV<'ctx> {
+        let size = count.get_size();
+        count.bvand(&z3::ast::BV::from_u64(
+            &self.context,
+            (size - 1) as u64,
+            size,
+        ))
+    }
+
+    fn bit_set(&'ctx self, value: &z3::ast::BV<'ctx>, bit: u32) -> z3::ast::Bool<'ctx> {
+        value.extract(bit, bit)._eq(&self.one(1))
+    }
+
+    fn sym_clz(&'ctx self, value: &z3::ast::BV<'ctx>) -> z3::ast::BV<'ctx> {
+        let size = value.get_size();
+        let mut count = z3::ast::BV::from_u64(&self.context, size as u64, size);
+        for bit in 0..size {
+            let leading = z3::ast::BV::from_u64(&self.context, (size - 1 - bit) as u64, size);
+            count = self.bit_set(value, bit).ite(&leading, &count);
+        }
+        count
+    }
+
+    fn sym_ctz(&'ctx self, value: &z3::ast::BV<'ctx>) -> z3::ast::BV<'ctx> {
+        let size = value.get_size();
+        let mut count = z3::ast::BV::from_u64(&self.context, size as u64, size);
+        for bit in (0..size).rev() {
+            let trailing = z3::ast::BV::from_u64(&self.context, bit as u64, size);
+            count = self.bit_set(value, bit).ite(&trailing, &count);
+        }
+        count
+    }
+
+    fn sym_popcnt(&'ctx self, value: &z3::ast::BV<'ctx>) -> z3::ast::BV<'ctx> {
+        let size = value.get_size();
+        let mut count = self.zero(size);
+        for bit in 0..size {
+            count = count.bvadd(&value.extract(bit, bit).zero_ext(size - 1));
+        }
+        count
+    }
+
     pub fn bin_conc(
         &'ctx self,
         op: ir::BinaryOp,
@@ -37,19 +282,29 @@ impl<'ctx, 'm> Context<'m> {
             ir::BinaryOp::I32Mul => ir::Value::I32(lhs.as_i32().wrapping_mul(rhs.as_i32())),
             ir::BinaryOp::I32DivS => match lhs.as_i32().checked_div(rhs.as_i32()) {
                 Some(value) => ir::Value::I32(value),
-                None => return Err(TrapReason::DivisionByZero),
+                None if rhs.as_i32() == 0 => return Err(TrapReason::DivisionByZero),
+                None => return Err(TrapReason::IntegerOverflow),
             },
             ir::BinaryOp::I32DivU => match (lhs.as_i32() as u32).checked_div(rhs.as_i32() as u32) {
                 Some(value) => ir::Value::I32(value as i32),
                 None => return Err(TrapReason::DivisionByZero),
             },
+            ir::BinaryOp::I32RemS => match rhs.as_i32() {
+                0 => return Err(TrapReason::DivisionByZero),
+                rhs => ir::Value::I32(lhs.as_i32().wrapping_rem(rhs)),
+            },
+            ir::BinaryOp::I32RemU => match (lhs.as_i32() as u32).checked_rem(rhs.as_i32() as u32) {
+                Some(value) => ir::Value::I32(value as i32),
+                None => return Err(TrapReason::DivisionByZero),
+            },
             ir::BinaryOp::I32And => ir::Value::I32(lhs.as_i32() & rhs.as_i32()),
+            ir::BinaryOp::I32Or => ir::Value::I32(lhs.as_i32() | rhs.as_i32()),
             ir::BinaryOp::I32Xor => ir::Value::I32(lhs.as_i32() ^ rhs.as_i32()),
             ir::BinaryOp::I32ShrU => {
-                ir::Value::I32(((lhs.as_i32() as u32) >> (rhs.as_i32() as u32)) as i32)
+                ir::Value::I32((lhs.as_i32() as u32).wrapping_shr(rhs.as_i32() as u32) as i32)
             }
-            ir::BinaryOp::I32ShrS => ir::Value::I32(lhs.as_i32() >> (rhs.as_i32() as u32)),
-            ir::BinaryOp::I32Shl => ir::Value::I32(lhs.as_i32() << (rhs.as_i32() as u32)),
+            ir::BinaryOp::I32ShrS => ir::Value::I32(lhs.as_i32().wrapping_shr(rhs.as_i32() as u32)),
+            ir::BinaryOp::I32Shl => ir::Value::I32(lhs.as_i32().wrapping_shl(rhs.as_i32() as u32)),
             ir::BinaryOp::I32Rotl => ir::Value::I32(lhs.as_i32().rotate_left(rhs.as_i32() as u32)),
             ir::BinaryOp::I32Rotr => ir::Value::I32(lhs.as_i32().rotate_right(rhs.as_i32() as u32)),
             ir::BinaryOp::I32LtU => {
@@ -69,7 +324,87 @@ impl<'ctx, 'm> Context<'m> {
             }
             ir::BinaryOp::I32GeS => ir::Value::I32(i32::from(lhs.as_i32() >= rhs.as_i32())),
             ir::BinaryOp::I32Eq => ir::Value::I32(i32::from(lhs.as_i32() == rhs.as_i32())),
-            _ => panic!(),
+            ir::BinaryOp::I32Ne => ir::Value::I32(i32::from(lhs.as_i32() != rhs.as_i32())),
+            ir::BinaryOp::I64Add => ir::Value::I64(lhs.as_i64().wrapping_add(rhs.as_i64())),
+            ir::BinaryOp::I64Sub => ir::Value::I64(lhs.as_i64().wrapping_sub(rhs.as_i64())),
+            ir::BinaryOp::I64Mul => ir::Value::I64(lhs.as_i64().wrapping_mul(rhs.as_i64())),
+            ir::BinaryOp::I64DivS => match lhs.as_i64().checked_div(rhs.as_i64()) {
+                Some(value) => ir::Value::I64(value),
+                None if rhs.as_i64() == 0 => return Err(TrapReason::DivisionByZero),
+                None => return Err(TrapReason::IntegerOverflow),
+            },
+            ir::BinaryOp::I64DivU => match (lhs.as_i64() as u64).checked_div(rhs.as_i64() as u64) {
+                Some(value) => ir::Value::I64(value as i64),
+                None => return Err(TrapReason::DivisionByZero),
+            },
+            ir::BinaryOp::I64RemS => match rhs.as_i64() {
+                0 => return Err(TrapReason::DivisionByZero),
+                rhs => ir::Value::I64(lhs.as_i64().wrapping_rem(rhs)),
+            },
+            ir::BinaryOp::I64RemU => match (lhs.as_i64() as u64).checked_rem(rhs.as_i64() as u64) {
+                Some(value) => ir::Value::I64(value as i64),
+                None => return Err(TrapReason::DivisionByZero),
+            },
+            ir::BinaryOp::I64And => ir::Value::I64(lhs.as_i64() & rhs.as_i64()),
+            ir::BinaryOp::I64Or => ir::Value::I64(lhs.as_i64() | rhs.as_i64()),
+            ir::BinaryOp::I64Xor => ir::Value::I64(lhs.as_i64() ^ rhs.as_i64()),
+            ir::BinaryOp::I64Shl => ir::Value::I64(lhs.as_i64().wrapping_shl(rhs.as_i64() as u32)),
+            ir::BinaryOp::I64ShrS => ir::Value::I64(lhs.as_i64().wrapping_shr(rhs.as_i64() as u32)),
+            ir::BinaryOp::I64ShrU => {
+                ir::Value::I64((lhs.as_i64() as u64).wrapping_shr(rhs.as_i64() as u32) as i64)
+            }
+            ir::BinaryOp::I64Rotl => ir::Value::I64(lhs.as_i64().rotate_left(rhs.as_i64() as u32)),
+            ir::BinaryOp::I64Rotr => ir::Value::I64(lhs.as_i64().rotate_right(rhs.as_i64() as u32)),
+            ir::BinaryOp::I64Eq => ir::Value::I32(i32::from(lhs.as_i64() == rhs.as_i64())),
+            ir::BinaryOp::I64Ne => ir::Value::I32(i32::from(lhs.as_i64() != rhs.as_i64())),
+            ir::BinaryOp::I64LtU => {
+                ir::Value::I32(i32::from((lhs.as_i64() as u64) < (rhs.as_i64() as u64)))
+            }
+            ir::BinaryOp::I64LtS => ir::Value::I32(i32::from(lhs.as_i64() < rhs.as_i64())),
+            ir::BinaryOp::I64GtU => {
+                ir::Value::I32(i32::from((lhs.as_i64() as u64) > (rhs.as_i64() as u64)))
+            }
+            ir::BinaryOp::I64GtS => ir::Value::I32(i32::from(lhs.as_i64() > rhs.as_i64())),
+            ir::BinaryOp::I64LeU => {
+                ir::Value::I32(i32::from((lhs.as_i64() as u64) <= (rhs.as_i64() as u64)))
+            }
+            ir::BinaryOp::I64LeS => ir::Value::I32(i32::from(lhs.as_i64() <= rhs.as_i64())),
+            ir::BinaryOp::I64GeU => {
+                ir::Value::I32(i32::from((lhs.as_i64() as u64) >= (rhs.as_i64() as u64)))
+            }
+            ir::BinaryOp::I64GeS => ir::Value::I32(i32::from(lhs.as_i64() >= rhs.as_i64())),
+            ir::BinaryOp::F32Add => ir::Value::F32(lhs.as_f32() + rhs.as_f32()),
+            ir::BinaryOp::F32Sub => ir::Value::F32(lhs.as_f32() - rhs.as_f32()),
+            ir::BinaryOp::F32Mul => ir::Value::F32(lhs.as_f32() * rhs.as_f32()),
+            ir::BinaryOp::F32Div => ir::Value::F32(lhs.as_f32() / rhs.as_f32()),
+            ir::BinaryOp::F32Min => {
+                ir::Value::F32(wasm_min(lhs.as_f32() as f64, rhs.as_f32() as f64) as f32)
+            }
+            ir::BinaryOp::F32Max => {
+                ir::Value::F32(wasm_max(lhs.as_f32() as f64, rhs.as_f32() as f64) as f32)
+            }
+            ir::BinaryOp::F32Copysign => ir::Value::F32(lhs.as_f32().copysign(rhs.as_f32())),
+            ir::BinaryOp::F32Eq => ir::Value::I32(i32::from(lhs.as_f32() == rhs.as_f32())),
+            ir::BinaryOp::F32Ne => ir::Value::I32(i32::from(lhs.as_f32() != rhs.as_f32())),
+            ir::BinaryOp::F32Lt => ir::Value::I32(i32::from(lhs.as_f32() < rhs.as_f32())),
+            ir::BinaryOp::F32Gt => ir::Value::I32(i32::from(lhs.as_f32() > rhs.as_f32())),
+            ir::BinaryOp::F32Le => ir::Value::I32(i32::from(lhs.as_f32() <= rhs.as_f32())),
+            ir::BinaryOp::F32Ge => ir::Value::I32(i32::from(lhs.as_f32() >= rhs.as_f32())),
+            ir::BinaryOp::F64Add => ir::Value::F64(lhs.as_f64() + rhs.as_f64()),
+            ir::BinaryOp::F64Sub => ir::Value::F64(lhs.as_f64() - rhs.as_f64()),
+            ir::BinaryOp::F64Mul => ir::Value::F64(lhs.as_f64() * rhs.as_f64()),
+            ir::BinaryOp::F64Div => ir::Value::F64(lhs.as_f64() / rhs.as_f64()),
+            ir::BinaryOp::F64Min => ir::Value::F64(wasm_min(lhs.as_f64(), rhs.as_f64())),
+            ir::BinaryOp::F64Max => ir::Value::F64(wasm_max(lhs.as_f64(), rhs.as_f64())),
+            ir::BinaryOp::F64Copysign => ir::Value::F64(lhs.as_f64().copysign(rhs.as_f64())),
+            ir::BinaryOp::F64Eq => ir::Value::I32(i32::from(lhs.as_f64() == rhs.as_f64())),
+            ir::BinaryOp::F64Ne => ir::Value::I32(i32::from(lhs.as_f64() != rhs.as_f64())),
+            ir::BinaryOp::F64Lt => ir::Value::I32(i32::from(lhs.as_f64() < rhs.as_f64())),
+            ir::BinaryOp::F64Gt => ir::Value::I32(i32::from(lhs.as_f64() > rhs.as_f64())),
+            ir::BinaryOp::F64Le => ir::Value::I32(i32::from(lhs.as_f64() <= rhs.as_f64())),
+            ir::BinaryOp::F64Ge => ir::Value::I32(i32::from(lhs.as_f64() >= rhs.as_f64())),
+            // The engine stops paths at splats, and no other vector op can run without a v128 operand
+            _ => unreachable!("{:?} is not modelled", op),
         }))
     }
 
@@ -85,9 +420,17 @@ impl<'ctx, 'm> Context<'m> {
             ir::BinaryOp::I32Mul => SymVal::I32(lhs.as_i32().bvmul(rhs.as_i32())),
             ir::BinaryOp::I32DivS => SymVal::I32(lhs.as_i32().bvsdiv(rhs.as_i32())),
             ir::BinaryOp::I32DivU => SymVal::I32(lhs.as_i32().bvudiv(rhs.as_i32())),
-            ir::BinaryOp::I32ShrS => SymVal::I32(lhs.as_i32().bvashr(rhs.as_i32())),
-            ir::BinaryOp::I32ShrU => SymVal::I32(lhs.as_i32().bvlshr(rhs.as_i32())),
-            ir::BinaryOp::I32Shl => SymVal::I32(lhs.as_i32().bvshl(rhs.as_i32())),
+            ir::BinaryOp::I32RemS => SymVal::I32(lhs.as_i32().bvsrem(rhs.as_i32())),
+            ir::BinaryOp::I32RemU => SymVal::I32(lhs.as_i32().bvurem(rhs.as_i32())),
+            ir::BinaryOp::I32ShrS => {
+                SymVal::I32(lhs.as_i32().bvashr(&self.shift_count(rhs.as_i32())))
+            }
+            ir::BinaryOp::I32ShrU => {
+                SymVal::I32(lhs.as_i32().bvlshr(&self.shift_count(rhs.as_i32())))
+            }
+            ir::BinaryOp::I32Shl => {
+                SymVal::I32(lhs.as_i32().bvshl(&self.shift_count(rhs.as_i32())))
+            }
             ir::BinaryOp::I32Rotl => SymVal::I32(lhs.as_i32().bvrotl(rhs.as_i32())),
             ir::BinaryOp::I32Rotr => SymVal::I32(lhs.as_i32().bvrotr(rhs.as_i32())),
             ir::BinaryOp::I32And => SymVal::I32(lhs.as_i32().bvand(rhs.as_i32())),
@@ -98,6 +441,11 @@ impl<'ctx, 'm> Context<'m> {
                     .bvslt(rhs.as_i32())
                     .ite(&self.one(32), &self.zero(32)),
             ),
+            ir::BinaryOp::I32LtU => SymVal::I32(
+                lhs.as_i32()
+                    .bvult(rhs.as_i32())
+                    .ite(&self.one(32), &self.zero(32)),
+            ),
             ir::BinaryOp::I32LeU => SymVal::I32(
                 lhs.as_i32()
                     .bvule(rhs.as_i32())
@@ -138,7 +486,65 @@ impl<'ctx, 'm> Context<'m> {
                     ._eq(rhs.as_i32())
                     .ite(&self.zero(32), &self.one(32)),
             ),
-            _ => todo!(),
+            ir::BinaryOp::I64Add => SymVal::I64(lhs.as_i64().bvadd(rhs.as_i64())),
+            ir::BinaryOp::I64Sub => SymVal::I64(lhs.as_i64().bvsub(rhs.as_i64())),
+            ir::BinaryOp::I64Mul => SymVal::I64(lhs.as_i64().bvmul(rhs.as_i64())),
+            ir::BinaryOp::I64DivS => SymVal::I64(lhs.as_i64().bvsdiv(rhs.as_i64())),
+            ir::BinaryOp::I64DivU => SymVal::I64(lhs.as_i64().bvudiv(rhs.as_i64())),
+            ir::BinaryOp::I64RemS => SymVal::I64(lhs.as_i64().bvsrem(rhs.as_i64())),
+            ir::BinaryOp::I64RemU => SymVal::I64(lhs.as_i64().bvurem(rhs.as_i64())),
+            ir::BinaryOp::I64ShrS => {
+                SymVal::I64(lhs.as_i64().bvashr(&self.shift_count(rhs.as_i64())))
+            }
+            ir::BinaryOp::I64ShrU => {
+                SymVal::I64(lhs.as_i64().bvlshr(&self.shift_count(rhs.as_i64())))
+            }
+            ir::BinaryOp::I64Shl => {
+                SymVal::I64(lhs.as_i64().bvshl(&self.shift_count(rhs.as_i64())))
+            }
+            ir::BinaryOp::I64Rotl => SymVal::I64(lhs.as_i64().bvrotl(rhs.as_i64())),
+            ir::BinaryOp::I64Rotr => SymVal::I64(lhs.as_i64().bvrotr(rhs.as_i64())),
+            ir::BinaryOp::I64And => SymVal::I64(lhs.as_i64().bvand(rhs.as_i64())),
+            ir::BinaryOp::I64Or => SymVal::I64(lhs.as_i64().bvor(rhs.as_i64())),
+            ir::BinaryOp::I64Xor => SymVal::I64(lhs.as_i64().bvxor(rhs.as_i64())),
+            ir::BinaryOp::I64Eq => self.bool_to_i32(&lhs.as_i64()._eq(rhs.as_i64())),
+            ir::BinaryOp::I64Ne => self.bool_to_i32(&lhs.as_i64()._eq(rhs.as_i64()).not()),
+            ir::BinaryOp::I64LtS => self.bool_to_i32(&lhs.as_i64().bvslt(rhs.as_i64())),
+            ir::BinaryOp::I64LtU => self.bool_to_i32(&lhs.as_i64().bvult(rhs.as_i64())),
+            ir::BinaryOp::I64LeS => self.bool_to_i32(&lhs.as_i64().bvsle(rhs.as_i64())),
+            ir::BinaryOp::I64LeU => self.bool_to_i32(&lhs.as_i64().bvule(rhs.as_i64())),
+            ir::BinaryOp::I64GtS => self.bool_to_i32(&lhs.as_i64().bvsgt(rhs.as_i64())),
+            ir::BinaryOp::I64GtU => self.bool_to_i32(&lhs.as_i64().bvugt(rhs.as_i64())),
+            ir::BinaryOp::I64GeS => self.bool_to_i32(&lhs.as_i64().bvsge(rhs.as_i64())),
+            ir::BinaryOp::I64GeU => self.bool_to_i32(&lhs.as_i64().bvuge(rhs.as_i64())),
+            ir::BinaryOp::F32Add => SymVal::F32(float::add(lhs.as_f32(), rhs.as_f32())),
+            ir::BinaryOp::F32Sub => SymVal::F32(float::sub(lhs.as_f32(), rhs.as_f32())),
+            ir::BinaryOp::F32Mul => SymVal::F32(float::mul(lhs.as_f32(), rhs.as_f32())),
+            ir::BinaryOp::F32Div => SymVal::F32(float::div(lhs.as_f32(), rhs.as_f32())),
+            ir::BinaryOp::F32Min => SymVal::F32(float::min(lhs.as_f32(), rhs.as_f32())),
+            ir::BinaryOp::F32Max => SymVal::F32(float::max(lhs.as_f32(), rhs.as_f32())),
+            ir::BinaryOp::F32Copysign => SymVal::F32(float::copysign(lhs.as_f32(), rhs.as_f32())),
+            ir::BinaryOp::F32Eq => self.bool_to_i32(&float::eq(lhs.as_f32(), rhs.as_f32())),
+            ir::BinaryOp::F32Ne => self.bool_to_i32(&float::eq(lhs.as_f32(), rhs.as_f32()).not()),
+            ir::BinaryOp::F32Lt => self.bool_to_i32(&float::lt(lhs.as_f32(), rhs.as_f32())),
+            ir::BinaryOp::F32Gt => self.bool_to_i32(&float::gt(lhs.as_f32(), rhs.as_f32())),
+            ir::BinaryOp::F32Le => self.bool_to_i32(&float::le(lhs.as_f32(), rhs.as_f32())),
+            ir::BinaryOp::F32Ge => self.bool_to_i32(&float::ge(lhs.as_f32(), rhs.as_f32())),
+            ir::BinaryOp::F64Add => SymVal::F64(float::add(lhs.as_f64(), rhs.as_f64())),
+            ir::BinaryOp::F64Sub => SymVal::F64(float::sub(lhs.as_f64(), rhs.as_f64())),
+            ir::BinaryOp::F64Mul => SymVal::F64(float::mul(lhs.as_f64(), rhs.as_f64())),
+            ir::BinaryOp::F64Div => SymVal::F64(float::div(lhs.as_f64(), rhs.as_f64())),
+            ir::BinaryOp::F64Min => SymVal::F64(float::min(lhs.as_f64(), rhs.as_f64())),
+            ir::BinaryOp::F64Max => SymVal::F64(float::max(lhs.as_f64(), rhs.as_f64())),
+            ir::BinaryOp::F64Copysign => SymVal::F64(float::copysign(lhs.as_f64(), rhs.as_f64())),
+            ir::BinaryOp::F64Eq => self.bool_to_i32(&float::eq(lhs.as_f64(), rhs.as_f64())),
+            ir::BinaryOp::F64Ne => self.bool_to_i32(&float::eq(lhs.as_f64(), rhs.as_f64()).not()),
+            ir::BinaryOp::F64Lt => self.bool_to_i32(&float::lt(lhs.as_f64(), rhs.as_f64())),
+            ir::BinaryOp::F64Gt => self.bool_to_i32(&float::gt(lhs.as_f64(), rhs.as_f64())),
+            ir::BinaryOp::F64Le => self.bool_to_i32(&float::le(lhs.as_f64(), rhs.as_f64())),
+            ir::BinaryOp::F64Ge => self.bool_to_i32(&float::ge(lhs.as_f64(), rhs.as_f64())),
+            // The engine stops paths at splats, and no other vector op can run without a v128 operand
+            _ => unreachable!("{:?} is not modelled", op),
         }
     }
 
@@ -165,13 +571,69 @@ impl<'ctx, 'm> Context<'m> {
     }
 
     pub fn un_conc(&'ctx self, op: ir::UnaryOp, operand: &ConcVal) -> Result<ConcVal, TrapReason> {
+        if let Some(truncation) = Truncation::from_op(op) {
+            return truncation.conc(operand);
+        }
+
         Ok(ConcVal(match op {
             ir::UnaryOp::I32Eqz => ir::Value::I32(i32::from(operand.as_i32() == 0)),
-            _ => unimplemented!(),
+            ir::UnaryOp::I32Clz => ir::Value::I32(operand.as_i32().leading_zeros() as i32),
+            ir::UnaryOp::I32Ctz => ir::Value::I32(operand.as_i32().trailing_zeros() as i32),
+            ir::UnaryOp::I32Popcnt => ir::Value::I32(operand.as_i32().count_ones() as i32),
+            ir::UnaryOp::I32Extend8S => ir::Value::I32(operand.as_i32() as i8 as i32),
+            ir::UnaryOp::I32Extend16S => ir::Value::I32(operand.as_i32() as i16 as i32),
+            ir::UnaryOp::I64Eqz => ir::Value::I32(i32::from(operand.as_i64() == 0)),
+            ir::UnaryOp::I64Clz => ir::Value::I64(operand.as_i64().leading_zeros() as i64),
+            ir::UnaryOp::I64Ctz => ir::Value::I64(operand.as_i64().trailing_zeros() as i64),
+            ir::UnaryOp::I64Popcnt => ir::Value::I64(operand.as_i64().count_ones() as i64),
+            ir::UnaryOp::I64Extend8S => ir::Value::I64(operand.as_i64() as i8 as i64),
+            ir::UnaryOp::I64Extend16S => ir::Value::I64(operand.as_i64() as i16 as i64),
+            ir::UnaryOp::I64Extend32S => ir::Value::I64(operand.as_i64() as i32 as i64),
+            ir::UnaryOp::F32Abs => ir::Value::F32(operand.as_f32().abs()),
+            ir::UnaryOp::F32Neg => ir::Value::F32(-operand.as_f32()),
+            ir::UnaryOp::F32Ceil => ir::Value::F32(operand.as_f32().ceil()),
+            ir::UnaryOp::F32Floor => ir::Value::F32(operand.as_f32().floor()),
+            ir::UnaryOp::F32Trunc => ir::Value::F32(operand.as_f32().trunc()),
+            ir::UnaryOp::F32Nearest => ir::Value::F32(operand.as_f32().round_ties_even()),
+            ir::UnaryOp::F32Sqrt => ir::Value::F32(operand.as_f32().sqrt()),
+            ir::UnaryOp::F64Abs => ir::Value::F64(operand.as_f64().abs()),
+            ir::UnaryOp::F64Neg => ir::Value::F64(-operand.as_f64()),
+            ir::UnaryOp::F64Ceil => ir::Value::F64(operand.as_f64().ceil()),
+            ir::UnaryOp::F64Floor => ir::Value::F64(operand.as_f64().floor()),
+            ir::UnaryOp::F64Trunc => ir::Value::F64(operand.as_f64().trunc()),
+            ir::UnaryOp::F64Nearest => ir::Value::F64(operand.as_f64().round_ties_even()),
+            ir::UnaryOp::F64Sqrt => ir::Value::F64(operand.as_f64().sqrt()),
+            ir::UnaryOp::I32ReinterpretF32 => ir::Value::I32(operand.as_f32().to_bits() as i32),
+            ir::UnaryOp::I64ReinterpretF64 => ir::Value::I64(operand.as_f64().to_bits() as i64),
+            ir::UnaryOp::F32ReinterpretI32 => {
+                ir::Value::F32(f32::from_bits(operand.as_i32() as u32))
+            }
+            ir::UnaryOp::F64ReinterpretI64 => {
+                ir::Value::F64(f64::from_bits(operand.as_i64() as u64))
+            }
+            ir::UnaryOp::I32WrapI64 => ir::Value::I32(operand.as_i64() as i32),
+            ir::UnaryOp::I64ExtendSI32 => ir::Value::I64(operand.as_i32() as i64),
+            ir::UnaryOp::I64ExtendUI32 => ir::Value::I64(operand.as_i32() as u32 as i64),
+            ir::UnaryOp::F32ConvertSI32 => ir::Value::F32(operand.as_i32() as f32),
+            ir::UnaryOp::F32ConvertUI32 => ir::Value::F32(operand.as_i32() as u32 as f32),
+            ir::UnaryOp::F32ConvertSI64 => ir::Value::F32(operand.as_i64() as f32),
+            ir::UnaryOp::F32ConvertUI64 => ir::Value::F32(operand.as_i64() as u64 as f32),
+            ir::UnaryOp::F64ConvertSI32 => ir::Value::F64(operand.as_i32() as f64),
+            ir::UnaryOp::F64ConvertUI32 => ir::Value::F64(operand.as_i32() as u32 as f64),
+            ir::UnaryOp::F64ConvertSI64 => ir::Value::F64(operand.as_i64() as f64),
+            ir::UnaryOp::F64ConvertUI64 => ir::Value::F64(operand.as_i64() as u64 as f64),
+            ir::UnaryOp::F32DemoteF64 => ir::Value::F32(operand.as_f64() as f32),
+            ir::UnaryOp::F64PromoteF32 => ir::Value::F64(operand.as_f32() as f64),
+            // The engine stops paths at splats, and no other vector op can run without a v128 operand
+            _ => unreachable!("{:?} is not modelled", op),
         }))
     }
 
     pub fn un_sym(&'ctx self, op: ir::UnaryOp, operand: &SymVal<'ctx>) -> SymVal<'ctx> {
+        if let Some(truncation) = Truncation::from_op(op) {
+            return truncation.sym(operand);
+        }
+
         match op {
             ir::UnaryOp::I32Eqz => SymVal::I32(
                 operand
@@ -179,7 +641,63 @@ impl<'ctx, 'm> Context<'m> {
                     ._eq(&self.zero(32))
                     .ite(&self.one(32), &self.zero(32)),
             ),
-            _ => unimplemented!(),
+            ir::UnaryOp::I32Clz => SymVal::I32(self.sym_clz(operand.as_i32())),
+            ir::UnaryOp::I32Ctz => SymVal::I32(self.sym_ctz(operand.as_i32())),
+            ir::UnaryOp::I32Popcnt => SymVal::I32(self.sym_popcnt(operand.as_i32())),
+            ir::UnaryOp::I32Extend8S => SymVal::I32(operand.as_i32().extract(7, 0).sign_ext(24)),
+            ir::UnaryOp::I32Extend16S => SymVal::I32(operand.as_i32().extract(15, 0).sign_ext(16)),
+            ir::UnaryOp::I64Eqz => self.bool_to_i32(&operand.as_i64()._eq(&self.zero(64))),
+            ir::UnaryOp::I64Clz => SymVal::I64(self.sym_clz(operand.as_i64())),
+            ir::UnaryOp::I64Ctz => SymVal::I64(self.sym_ctz(operand.as_i64())),
+            ir::UnaryOp::I64Popcnt => SymVal::I64(self.sym_popcnt(operand.as_i64())),
+            ir::UnaryOp::I64Extend8S => SymVal::I64(operand.as_i64().extract(7, 0).sign_ext(56)),
+            ir::UnaryOp::I64Extend16S => SymVal::I64(operand.as_i64().extract(15, 0).sign_ext(48)),
+            ir::UnaryOp::I64Extend32S => SymVal::I64(operand.as_i64().extract(31, 0).sign_ext(32)),
+            ir::UnaryOp::F32Abs => SymVal::F32(float::abs(operand.as_f32())),
+            ir::UnaryOp::F32Neg => SymVal::F32(float::neg(operand.as_f32())),
+            ir::UnaryOp::F32Ceil => SymVal::F32(float::round(operand.as_f32(), Rounding::Ceil)),
+            ir::UnaryOp::F32Floor => SymVal::F32(float::round(operand.as_f32(), Rounding::Floor)),
+            ir::UnaryOp::F32Trunc => SymVal::F32(float::round(operand.as_f32(), Rounding::Trunc)),
+            ir::UnaryOp::F32Nearest => {
+                SymVal::F32(float::round(operand.as_f32(), Rounding::Nearest))
+            }
+            ir::UnaryOp::F32Sqrt => SymVal::F32(float::sqrt(operand.as_f32())),
+            ir::UnaryOp::F64Abs => SymVal::F64(float::abs(operand.as_f64())),
+            ir::UnaryOp::F64Neg => SymVal::F64(float::neg(operand.as_f64())),
+            ir::UnaryOp::F64Ceil => SymVal::F64(float::round(operand.as_f64(), Rounding::Ceil)),
+            ir::UnaryOp::F64Floor => SymVal::F64(float::round(operand.as_f64(), Rounding::Floor)),
+            ir::UnaryOp::F64Trunc => SymVal::F64(float::round(operand.as_f64(), Rounding::Trunc)),
+            ir::UnaryOp::F64Nearest => {
+                SymVal::F64(float::round(operand.as_f64(), Rounding::Nearest))
+            }
+            ir::UnaryOp::F64Sqrt => SymVal::F64(float::sqrt(operand.as_f64())),
+            ir::UnaryOp::I32ReinterpretF32 => SymVal::I32(float::to_bits(operand.as_f32())),
+            ir::UnaryOp::I64ReinterpretF64 => SymVal::I64(float::to_bits(operand.as_f64())),
+            ir::UnaryOp::F32ReinterpretI32 => SymVal::F32(float::from_bits(operand.as_i32())),
+            ir::UnaryOp::F64ReinterpretI64 => SymVal::F64(float::from_bits(operand.as_i64())),
+            ir::UnaryOp::I32WrapI64 => SymVal::I32(operand.as_i64().extract(31, 0)),
+            ir::UnaryOp::I64ExtendSI32 => SymVal::I64(operand.as_i32().sign_ext(32)),
+            ir::UnaryOp::I64ExtendUI32 => SymVal::I64(operand.as_i32().zero_ext(32)),
+            ir::UnaryOp::F32ConvertSI32 => SymVal::F32(float::from_int(operand.as_i32(), 32, true)),
+            ir::UnaryOp::F32ConvertUI32 => {
+                SymVal::F32(float::from_int(operand.as_i32(), 32, false))
+            }
+            ir::UnaryOp::F32ConvertSI64 => SymVal::F32(float::from_int(operand.as_i64(), 32, true)),
+            ir::UnaryOp::F32ConvertUI64 => {
+                SymVal::F32(float::from_int(operand.as_i64(), 32, false))
+            }
+            ir::UnaryOp::F64ConvertSI32 => SymVal::F64(float::from_int(operand.as_i32(), 64, true)),
+            ir::UnaryOp::F64ConvertUI32 => {
+                SymVal::F64(float::from_int(operand.as_i32(), 64, false))
+            }
+            ir::UnaryOp::F64ConvertSI64 => SymVal::F64(float::from_int(operand.as_i64(), 64, true)),
+            ir::UnaryOp::F64ConvertUI64 => {
+                SymVal::F64(float::from_int(operand.as_i64(), 64, false))
+            }
+            ir::UnaryOp::F32DemoteF64 => SymVal::F32(float::convert(operand.as_f64(), 32)),
+            ir::UnaryOp::F64PromoteF32 => SymVal::F64(float::convert(operand.as_f32(), 64)),
+            // The engine stops paths at splats, and no other vector op can run without a v128 operand
+            _ => unreachable!("{:?} is not modelled", op),
         }
     }
 
@@ -188,7 +706,7 @@ impl<'ctx, 'm> Context<'m> {
         op: ir::UnaryOp,
         operand: &Val<'ctx>,
     ) -> Result<Val<'ctx>, TrapReason> {
-        Ok(match (operand) {
+        Ok(match operand {
             Val::Conc(val) => Val::Conc(self.un_conc(op, val)?),
             Val::Sym(val) => Val::Sym(self.un_sym(op, val)),
         })
@@ -1,21 +1,45 @@
+use crate::budget::{Budget, Limit};
 use crate::checks::Check;
 use crate::context::Context;
 use crate::flow::{compute_info, Info, Loc};
-use crate::memory::Memory;
+use crate::host::{HostModel, HostResult, UnconstrainedModel};
+use crate::loops::LoopInfo;
+use crate::memory::{effective_address, Memory};
+use crate::merge::can_merge;
 use crate::reporter::Reporter;
+use crate::search::{BreadthFirstSearch, SearchStrategy};
 use crate::state::{Execution, Frame, State, Status, TrapReason};
 use crate::value::{ConcVal, SymVal, Val};
-use log::{info, trace};
-use std::collections::{HashMap, VecDeque};
+use log::{info, trace, warn};
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Instant;
 use walrus::{ir, InstrLocId};
 use z3::ast::Ast;
 
 pub struct Engine<'ctx, 'm> {
     pub context: &'ctx Context<'m>,
     info: Vec<Option<Info>>,
-    executions: VecDeque<Execution<'ctx>>,
+    executions: Box<dyn SearchStrategy<'ctx> + 'ctx>,
     checks: Vec<Box<dyn Check<'ctx> + 'ctx>>,
-    max_hotness: usize,
+    host_models: HashMap<(String, String), Box<dyn HostModel<'ctx> + 'ctx>>,
+    pub(crate) max_hotness: usize,
+    symbolic_globals: bool,
+    max_merge_size: Option<usize>,
+    budget: Budget,
+    run_start: Option<Instant>,
+    func_start: Option<Instant>,
+    paths: usize,                          // paths finished for the current function
+    out_of_budget: Vec<Execution<'ctx>>,   // executions turned away at the live execution limit
+    pub(crate) held: Vec<Execution<'ctx>>, // executions waiting at a join point for others to merge with
+}
+
+/// Whether values of the type are modelled, which leaves out references and vectors
+fn is_modelled(ty: walrus::ValType) -> bool {
+    matches!(
+        ty,
+        walrus::ValType::I32 | walrus::ValType::I64 | walrus::ValType::F32 | walrus::ValType::F64
+    )
 }
 
 pub fn as_local_func(func: &walrus::Function) -> Option<&walrus::LocalFunction> {
@@ -30,9 +54,18 @@ impl<'ctx, 'm> Engine<'ctx, 'm> {
         Engine {
             context,
             info: vec![None; context.module.funcs.iter().count()],
-            executions: VecDeque::new(),
+            executions: Box::new(BreadthFirstSearch::new()),
             checks: Vec::new(),
+            host_models: HashMap::new(),
             max_hotness: 1,
+            symbolic_globals: false,
+            max_merge_size: None,
+            budget: Budget::new(),
+            run_start: None,
+            func_start: None,
+            paths: 0,
+            out_of_budget: Vec::new(),
+            held: Vec::new(),
         }
     }
 
@@ -40,15 +73,52 @@ impl<'ctx, 'm> Engine<'ctx, 'm> {
         self.max_hotness = max_hotness;
     }
 
+    /// Merges executions that meet at the same location when the merged path condition and values stay within
+    /// `max_size` term nodes each
+    pub fn set_merging(&mut self, max_size: Option<usize>) {
+        self.max_merge_size = max_size;
+    }
+
+    pub fn set_budget(&mut self, budget: Budget) {
+        self.context
+            .set_solver_budget(budget.max_queries, budget.query_timeout);
+        self.budget = budget;
+    }
+
+    /// Sets the order in which pending executions are explored, breadth-first by default
+    pub fn set_search_strategy(&mut self, strategy: Box<dyn SearchStrategy<'ctx> + 'ctx>) {
+        self.executions = strategy;
+    }
+
+    /// Makes imported globals and mutable exported globals symbolic instead of using their initial values
+    pub fn set_symbolic_globals(&mut self, symbolic_globals: bool) {
+        self.symbolic_globals = symbolic_globals;
+    }
+
     pub fn add_check(&mut self, check: Box<dyn Check<'ctx> + 'ctx>) {
         self.checks.push(check);
     }
 
+    /// Registers the model used for calls to the import `module`.`name`
+    pub fn add_host_model(
+        &mut self,
+        module: &str,
+        name: &str,
+        model: Box<dyn HostModel<'ctx> + 'ctx>,
+    ) {
+        self.host_models
+            .insert((module.to_string(), name.to_string()), model);
+    }
+
+    /// Symbolic values of the params of `func`, leaving out those of unmodelled types
     pub fn get_inputs(&self, func: &'m walrus::LocalFunction) -> HashMap<ir::LocalId, Val<'ctx>> {
         let mut inputs = HashMap::new();
         for param_id in func.args.iter() {
             let param = self.context.module.locals.get(*param_id);
             let param_ty = param.ty();
+            if !is_modelled(param_ty) {
+                continue;
+            }
             let symbolic_param = Val::Sym(SymVal::from_valtype(
                 &self.context.context,
                 param_ty,
@@ -59,6 +129,66 @@ impl<'ctx, 'm> Engine<'ctx, 'm> {
         inputs
     }
 
+    fn is_symbolic_global(&self, global: &walrus::Global) -> bool {
+        if !self.symbolic_globals || !is_modelled(global.ty) {
+            return false;
+        }
+
+        match global.kind {
+            walrus::GlobalKind::Import(_) => true,
+            walrus::GlobalKind::Local(_) => {
+                global.mutable
+                    && self.context.module.exports.iter().any(|export| {
+                        matches!(export.item, walrus::ExportItem::Global(id) if id == global.id())
+                    })
+            }
+        }
+    }
+
+    /// Symbolic values of the globals that are treated as inputs
+    pub fn get_global_inputs(&self) -> HashMap<walrus::GlobalId, Val<'ctx>> {
+        let mut inputs = HashMap::new();
+        for global in self.context.module.globals.iter() {
+            if self.is_symbolic_global(global) {
+                let symbolic_global = Val::Sym(SymVal::from_valtype(
+                    &self.context.context,
+                    global.ty,
+                    format!("global{}", global.id().index()),
+                ));
+                inputs.insert(global.id(), symbolic_global);
+            }
+        }
+        inputs
+    }
+
+    pub fn get_initial_globals(&self) -> HashMap<walrus::GlobalId, Val<'ctx>> {
+        let mut globals = self.get_global_inputs();
+        for global in self.context.module.globals.iter() {
+            if globals.contains_key(&global.id()) || !is_modelled(global.ty) {
+                continue;
+            }
+
+            let value = match &global.kind {
+                walrus::GlobalKind::Import(_) => Val::Conc(ConcVal::from_valtype(global.ty)),
+                walrus::GlobalKind::Local(walrus::InitExpr::Value(value)) => {
+                    Val::Conc(ConcVal(*value))
+                }
+                // Only imported globals may appear in an init expression, and they come first. A global the module
+                // doesn't import can't be resolved, so it is left without a value like a reference.
+                walrus::GlobalKind::Local(walrus::InitExpr::Global(other)) => {
+                    match globals.get(other) {
+                        Some(value) => value.clone(),
+                        None => continue,
+                    }
+                }
+                // References are not modelled
+                walrus::GlobalKind::Local(_) => continue,
+            };
+            globals.insert(global.id(), value);
+        }
+        globals
+    }
+
     pub fn initialize(&mut self) {
         for func in self.context.module.funcs.iter() {
             let info = match &func.kind {
@@ -70,36 +200,90 @@ impl<'ctx, 'm> Engine<'ctx, 'm> {
         }
     }
 
-    pub fn get_initial_execution(
-        &mut self,
+    /// Frame for entering `func` with symbolic params
+    fn get_entry_frame(
+        &self,
         func: &'m walrus::LocalFunction,
         id: walrus::FunctionId,
-    ) -> Execution<'ctx> {
+    ) -> Frame<'ctx> {
         let inputs = self.get_inputs(func);
         let mut frame = Frame::new(id, None);
 
-        frame.locals.extend(inputs.clone());
+        frame.locals.extend(inputs);
 
-        let info = self.info[id.index()].as_ref().unwrap();
-        for local in info.locals.iter() {
-            if !frame.locals.contains_key(local) {
-                let local_ty = self.context.module.locals.get(*local).ty();
-                frame
-                    .locals
-                    .insert(*local, Val::Conc(ConcVal::from_valtype(local_ty)));
-            }
-        }
+        self.init_locals(&mut frame);
+        frame
+    }
 
+    /// State of a freshly instantiated module, before its start function runs, along with the trap instantiation
+    /// ends in, if any
+    fn get_initial_state(&self) -> (State<'ctx>, Option<TrapReason>) {
         let mut state = State::new();
-        state.call_stack.push(frame);
+        state.globals = self.get_initial_globals();
         for memory in self.context.module.memories.iter() {
-            state.memory = Some(Memory::new(&self.context.context, memory.initial));
+            state.memories.insert(
+                memory.id(),
+                Memory::new(&self.context.context, memory.initial, memory.maximum),
+            );
         }
+        // Element segments are applied before data segments, and a failing segment stops instantiation
+        let trap = self
+            .init_tables(&mut state)
+            .and_then(|()| self.init_data(&mut state))
+            .err();
+        (state, trap)
+    }
 
-        let execution = Execution::new(state, func.entry_block());
+    pub fn get_initial_execution(
+        &mut self,
+        func: &'m walrus::LocalFunction,
+        id: walrus::FunctionId,
+    ) -> Execution<'ctx> {
+        let (mut state, trap) = self.get_initial_state();
+        state.call_stack.push(self.get_entry_frame(func, id));
+        let mut execution = Execution::new(state, func.entry_block());
+        if let Some(trap) = trap {
+            execution.status = Status::Trap(trap);
+        }
         execution
     }
 
+    /// Explores `func` as the host would call it: after instantiation, including the module's start function
+    pub fn get_entry_executions(
+        &mut self,
+        func: &'m walrus::LocalFunction,
+        id: walrus::FunctionId,
+    ) -> Vec<Execution<'ctx>> {
+        let start_id = match self.context.module.start {
+            Some(start_id) if start_id != id => start_id,
+            _ => return self.get_func_executions(func, id, None),
+        };
+        let start_func = as_local_func(self.context.module.funcs.get(start_id)).unwrap();
+        let start_execution = self.get_initial_execution(start_func, start_id);
+
+        // Paths that fail or exit during instantiation never reach the entry point
+        let (instantiated, mut executions): (Vec<_>, Vec<_>) = self
+            .get_func_executions(start_func, start_id, Some(start_execution))
+            .into_iter()
+            .partition(|execution| execution.status == Status::Complete);
+
+        for mut execution in instantiated {
+            execution.state.call_stack.clear();
+            execution
+                .state
+                .call_stack
+                .push(self.get_entry_frame(func, id));
+            execution.cur_block = func.entry_block();
+            execution.cur_location = None;
+            execution.status = Status::None;
+            execution.hotness.clear();
+            execution.widened.clear();
+            self.push_execution(execution);
+        }
+        executions.extend(self.collect_executions());
+        executions
+    }
+
     pub fn get_func_executions(
         &mut self,
         func: &'m walrus::LocalFunction,
@@ -114,6 +298,7 @@ impl<'ctx, 'm> Engine<'ctx, 'm> {
         for check in &self.checks {
             execution.add_check(dyn_clone::clone_box(&**check));
         }
+        execution.host_models = self.host_models.clone();
 
         self.push_execution(execution);
         self.collect_executions()
@@ -127,8 +312,33 @@ impl<'ctx, 'm> Engine<'ctx, 'm> {
     ) {
         info!("Analyzing function #{}", name);
 
-        let mut executions = self.get_func_executions(func, id, None);
+        self.start_budget();
+        let executions = self.get_func_executions(func, id, None);
+        self.report(func, name, executions);
+    }
+
+    /// Analyzes `func` as an entry point called on an instantiated module
+    pub fn analyze_entry(
+        &mut self,
+        func: &'m walrus::LocalFunction,
+        id: walrus::FunctionId,
+        name: &str,
+    ) {
+        info!("Analyzing entry point #{}", name);
+
+        self.start_budget();
+        let executions = self.get_entry_executions(func, id);
+        self.report(func, name, executions);
+    }
+
+    fn report(
+        &self,
+        func: &'m walrus::LocalFunction,
+        name: &str,
+        mut executions: Vec<Execution<'ctx>>,
+    ) {
         let inputs = self.get_inputs(func);
+        let globals = self.get_global_inputs();
         executions
             .iter_mut()
             .for_each(|execution| execution.state.simplify());
@@ -139,61 +349,536 @@ impl<'ctx, 'm> Engine<'ctx, 'm> {
 
         let mut completed_executions = executions
             .into_iter()
-            .filter(|execution| matches!(execution.status, Status::Complete | Status::Trap(_)))
+            .filter(|execution| {
+                matches!(
+                    execution.status,
+                    Status::Complete | Status::Exited | Status::Trap(_) | Status::OutOfBudget(_)
+                )
+            })
             .collect();
 
-        reporter.report_checks(self.context, &inputs, &mut completed_executions);
+        reporter.report_checks(self.context, &inputs, &globals, &mut completed_executions);
+    }
+
+    pub fn push_execution(&mut self, mut execution: Execution<'ctx>) {
+        if let Some(max_size) = self.max_merge_size {
+            let context = self.context;
+            let other = match self
+                .held
+                .iter()
+                .position(|other| can_merge(context, other, &execution, max_size))
+            {
+                Some(index) => Some(self.held.swap_remove(index)),
+                None => self
+                    .executions
+                    .take(&mut |other| can_merge(context, other, &execution, max_size)),
+            };
+            if let Some(other) = other {
+                trace!("Merging execution #{} into #{}", execution.id, other.id);
+                execution = self.merge_executions(other, execution);
+            }
+
+            // A path leaving a block waits there while another one is still inside it, so that they meet whatever
+            // order the search strategy explores them in
+            if execution.status == Status::None
+                && self.is_join_point(&execution)
+                && self.has_pending_partner(&execution)
+            {
+                self.held.push(execution);
+                return;
+            }
+        }
+
+        if execution.status == Status::None {
+            if let Some(max) = self.budget.max_live_executions {
+                if self.executions.len() >= max {
+                    execution.status = Status::OutOfBudget(Limit::LiveExecutions);
+                    self.out_of_budget.push(execution);
+                    return;
+                }
+            }
+        }
+        self.executions.push(execution);
+    }
+
+    /// Restarts the per-function budgets, and the run's clock on first use
+    fn start_budget(&mut self) {
+        let now = Instant::now();
+        self.run_start.get_or_insert(now);
+        self.func_start = Some(now);
+        self.paths = 0;
     }
 
-    pub fn push_execution(&mut self, execution: Execution<'ctx>) {
-        self.executions.push_back(execution);
+    /// The first budget that has run out, if any
+    fn get_exhausted_limit(&self) -> Option<Limit> {
+        let elapsed = |start: Option<Instant>| start.map(|start| start.elapsed());
+        let out_of_time = |start: Option<Instant>, limit: Option<std::time::Duration>| matches!((elapsed(start), limit), (Some(elapsed), Some(limit)) if elapsed >= limit);
+
+        if out_of_time(self.func_start, self.budget.func_time)
+            || out_of_time(self.run_start, self.budget.total_time)
+        {
+            Some(Limit::Time)
+        } else if matches!(self.budget.max_paths, Some(max) if self.paths >= max) {
+            Some(Limit::Paths)
+        } else if self.context.queries_exhausted() {
+            Some(Limit::Queries)
+        } else {
+            None
+        }
     }
 
     fn collect_executions(&mut self) -> Vec<Execution<'ctx>> {
+        if self.func_start.is_none() {
+            self.start_budget();
+        }
+
         let mut completed_executions = Vec::<Execution>::new();
-        while let Some(execution) = self.executions.pop_front() {
-            match self.step_execution(execution) {
-                Some(execution) => {
+        while let Some(mut execution) = self.next_execution() {
+            // Once a budget runs out, the remaining executions are drained unfinished
+            if execution.status == Status::None {
+                if let Some(limit) = self.get_exhausted_limit() {
+                    execution.status = Status::OutOfBudget(limit);
                     completed_executions.push(execution);
+                    continue;
                 }
-                None => (),
+            }
+
+            if let Some(execution) = self.step_execution(execution) {
+                self.paths += 1;
+                completed_executions.push(execution);
             }
         }
 
+        completed_executions.append(&mut self.out_of_budget);
         completed_executions
     }
 
+    /// Whether `len` bytes at `base_index + offset` lie within the given memory
+    pub(crate) fn get_in_bounds(
+        &self,
+        execution: &Execution<'ctx>,
+        memory: walrus::MemoryId,
+        base_index: &Val<'ctx>,
+        offset: u32,
+        len: &Val<'ctx>,
+    ) -> z3::ast::Bool<'ctx> {
+        let address = effective_address(self.context, base_index, offset);
+        execution.state.memories[&memory]
+            .out_of_bounds(self.context, &address, len)
+            .not()
+    }
+
+    /// Whether `condition` and its negation can each hold on the path, only asking the solver if it is not already decided
+    pub(crate) fn get_feasible_sides(
+        &self,
+        execution: &mut Execution<'ctx>,
+        condition: &z3::ast::Bool<'ctx>,
+    ) -> (bool, bool) {
+        let condition = condition.simplify();
+        match condition.as_bool() {
+            Some(value) => (value, !value),
+            None => (
+                execution.is_feasible(self.context, &condition),
+                execution.is_feasible(self.context, &condition.not()),
+            ),
+        }
+    }
+
+    /// Forks off a path that traps when `in_bounds` can be false, and constrains `execution` to the in-bounds case.
+    /// Returns false if the access always traps, in which case `execution` itself is the trapping path.
+    pub(crate) fn do_bounds_check(
+        &mut self,
+        execution: &mut Execution<'ctx>,
+        in_bounds: z3::ast::Bool<'ctx>,
+    ) -> bool {
+        self.do_trap_check(execution, in_bounds, TrapReason::MemoryOutOfBounds)
+    }
+
+    /// Forks off a path that traps when an atomic access at `base_index + offset` can be misaligned
+    pub(crate) fn do_alignment_check(
+        &mut self,
+        execution: &mut Execution<'ctx>,
+        base_index: &Val<'ctx>,
+        offset: u32,
+        bytes: u32,
+    ) -> bool {
+        if bytes == 1 {
+            return true;
+        }
+        let address = effective_address(self.context, base_index, offset);
+        let aligned = address
+            .extract(bytes.trailing_zeros() - 1, 0)
+            ._eq(&self.zero(bytes.trailing_zeros()));
+        self.do_trap_check(execution, aligned, TrapReason::UnalignedAtomic)
+    }
+
+    /// Forks off a path that traps with `reason` when `condition` can be false, and constrains `execution` to the case
+    /// where it holds. Returns false if it never holds, in which case `execution` itself is the trapping path.
+    pub(crate) fn do_trap_check(
+        &mut self,
+        execution: &mut Execution<'ctx>,
+        condition: z3::ast::Bool<'ctx>,
+        reason: TrapReason,
+    ) -> bool {
+        let condition = condition.simplify();
+        match self.get_feasible_sides(execution, &condition) {
+            (true, false) => true,
+            (false, _) => {
+                execution.status = Status::Trap(reason);
+                false
+            }
+            (true, true) => {
+                let mut trap_execution = Execution::from(execution, self.context);
+                trap_execution.add_constraint(condition.not());
+
+                trace!(
+                    "Forking execution #{} on {:?} -> [ok: #{}/trap: #{}]",
+                    execution.id,
+                    reason,
+                    execution.id,
+                    trap_execution.id
+                );
+
+                trap_execution.status = Status::Trap(reason);
+                self.push_execution(trap_execution);
+                execution.add_constraint(condition);
+                true
+            }
+        }
+    }
+
+    /// Number of params and results of a block
+    pub(crate) fn get_block_arity(
+        &self,
+        func_id: walrus::FunctionId,
+        seq_id: &ir::InstrSeqId,
+    ) -> (usize, usize) {
+        let func = as_local_func(self.context.module.funcs.get(func_id)).unwrap();
+        match func.block(*seq_id).ty {
+            ir::InstrSeqType::Simple(result) => (0, result.iter().count()),
+            ir::InstrSeqType::MultiValue(ty) => {
+                let ty = self.context.module.types.get(ty);
+                (ty.params().len(), ty.results().len())
+            }
+        }
+    }
+
     fn do_jump_to_seq(&self, execution: &mut Execution<'ctx>, seq_id: &ir::InstrSeqId) {
+        let frame = execution.state.call_stack.last_mut().unwrap();
+        let (params, _) = self.get_block_arity(frame.func, seq_id);
+        frame
+            .heights
+            .insert(*seq_id, frame.value_stack.len() - params);
+
         execution.cur_block = *seq_id;
         execution.cur_location = None;
     }
 
+    /// Leaves the current function with its results, resuming the caller or completing the execution
+    fn do_return(&mut self, mut execution: Execution<'ctx>) -> Option<Execution<'ctx>> {
+        execution.advance = false;
+        let mut old_frame = execution.state.call_stack.pop().unwrap();
+        let func = self.context.module.funcs.get(old_frame.func);
+        let num_results = self.context.module.types.get(func.ty()).results().len();
+        let results = old_frame
+            .value_stack
+            .split_off(old_frame.value_stack.len() - num_results);
+
+        match execution.state.call_stack.last_mut() {
+            None => {
+                old_frame.value_stack = results;
+                execution.status = Status::Complete;
+                execution.state.call_stack.push(old_frame);
+                Some(execution)
+            }
+            Some(prev_frame) => {
+                let ret = old_frame.ret.unwrap();
+                execution.cur_block = ret.block;
+                execution.cur_location = Some(InstrLocId::new(ret.loc));
+                execution.advance = true;
+                prev_frame.value_stack.extend(results);
+                self.push_execution(execution);
+                None
+            }
+        }
+    }
+
+    /// Analysis of the loop an execution is at the start of, if any
+    pub(crate) fn get_loop_info(&self, execution: &Execution<'ctx>) -> Option<&LoopInfo> {
+        let func = execution.state.call_stack.last()?.func;
+        self.info[func.index()]
+            .as_ref()?
+            .loops
+            .get(&execution.cur_block)
+    }
+
+    /// Next execution to step, as picked by the search strategy once the held executions nothing can meet any more
+    /// are back in it
+    fn next_execution(&mut self) -> Option<Execution<'ctx>> {
+        let settled = (0..self.held.len())
+            .filter(|&index| !self.has_pending_partner(&self.held[index]))
+            .collect::<Vec<_>>();
+        for index in settled.into_iter().rev() {
+            let execution = self.held.swap_remove(index);
+            self.push_execution(execution);
+        }
+        self.executions.pop().or_else(|| self.release_held())
+    }
+
+    /// Whether another execution is still inside a block that the held or joining `execution` has left, and so
+    /// could come to merge with it
+    fn has_pending_partner(&self, execution: &Execution<'ctx>) -> bool {
+        let mut is_partner = |other: &Execution<'ctx>| self.is_inside_left_block(execution, other);
+        self.executions.any(&mut is_partner) || self.held.iter().any(is_partner)
+    }
+
+    /// Whether `other` is in the same call as `execution`, or a call made from it, inside a block whose end is where
+    /// `execution` is
+    fn is_inside_left_block(&self, execution: &Execution<'ctx>, other: &Execution<'ctx>) -> bool {
+        let frames = &execution.state.call_stack;
+        let depth = frames.len();
+        let same_call = other.state.call_stack.len() >= depth
+            && frames.iter().zip(&other.state.call_stack).all(|(a, b)| {
+                a.func == b.func
+                    && a.ret.map(|ret| (ret.block, ret.loc))
+                        == b.ret.map(|ret| (ret.block, ret.loc))
+            });
+        let info = self.info[frames[depth - 1].func.index()].as_ref();
+        let (join, info) = match (execution.cur_location, info) {
+            (Some(join), Some(info)) if same_call => (join, info),
+            _ => return false,
+        };
+
+        // Within this function, a deeper call is at the block of its call instruction
+        let mut block = match other.state.call_stack.get(depth) {
+            None => Some(other.cur_block),
+            Some(callee) => callee.ret.map(|ret| ret.block),
+        };
+        while let Some(seq) = block {
+            if matches!(info.ends.get(&seq), Some(end) if end.loc == join.data() && end.block == execution.cur_block)
+            {
+                return true;
+            }
+            block = info.parents.get(&seq).copied();
+        }
+        false
+    }
+
+    /// Whether the execution has just left a block, where paths that forked inside it can meet again
+    fn is_join_point(&self, execution: &Execution<'ctx>) -> bool {
+        let (frame, loc) = match (execution.state.call_stack.last(), execution.cur_location) {
+            (Some(frame), Some(loc)) => (frame, loc),
+            _ => return false,
+        };
+        matches!(&self.info[frame.func.index()], Some(info) if info.joins.contains(&loc.data()))
+    }
+
+    /// Returns true if the branch targets the function body, which leaves the function
     fn do_branch(&self, execution: &mut Execution<'ctx>, block: &ir::InstrSeqId) -> bool {
         let info = self.info[execution.state.call_stack.last().unwrap().func.index()]
             .as_ref()
             .unwrap();
 
-        let block_loc = match info.ends.get(block) {
+        // The function body has no label instruction, so branching to it leaves the function
+        let block_instr = match info.types.get(block) {
             None => return true,
-            Some(end) => end,
+            Some(instr) => instr,
         };
-        let block_instr = info.types.get(block).unwrap();
+
+        // Only the label's operands survive the branch
+        let frame = execution.state.call_stack.last_mut().unwrap();
+        let (params, results) = self.get_block_arity(frame.func, block);
+        let arity = match block_instr {
+            ir::Instr::Loop(_) => params,
+            _ => results,
+        };
+        let height = *frame.heights.get(block).unwrap();
+        let values = frame.value_stack.split_off(frame.value_stack.len() - arity);
+        frame.value_stack.truncate(height);
+        frame.value_stack.extend(values);
 
         match block_instr {
-            ir::Instr::Block(_) => {
-                execution.cur_block = block_loc.block;
-                execution.cur_location = Some(InstrLocId::new(block_loc.loc));
-            }
+            ir::Instr::Block(_) | ir::Instr::IfElse(_) => match info.ends.get(block) {
+                // Nothing follows the block, so leaving it leaves the function
+                None => return true,
+                Some(block_loc) => {
+                    execution.cur_block = block_loc.block;
+                    execution.cur_location = Some(InstrLocId::new(block_loc.loc));
+                }
+            },
+            // A loop label is its start, so it doesn't matter whether anything follows the loop
             ir::Instr::Loop(_) => {
                 execution.cur_block = *block;
                 execution.cur_location = None;
             }
             _ => unreachable!(),
         }
-        return false;
+        false
+    }
+
+    /// Groups the entries of a `br_table` by target, pairing each target with the selector values that reach it
+    fn get_br_table_targets(
+        &self,
+        selector: &z3::ast::BV<'ctx>,
+        blocks: &[ir::InstrSeqId],
+        default: &ir::InstrSeqId,
+    ) -> Vec<(ir::InstrSeqId, z3::ast::Bool<'ctx>)> {
+        let context = &self.context.context;
+        let constant = |value: usize| z3::ast::BV::from_u64(context, value as u64, 32);
+
+        let mut targets: Vec<(ir::InstrSeqId, Vec<z3::ast::Bool<'ctx>>)> = Vec::new();
+        let mut add_condition =
+            |block: ir::InstrSeqId, condition: z3::ast::Bool<'ctx>| match targets
+                .iter_mut()
+                .find(|(target, _)| *target == block)
+            {
+                Some((_, conditions)) => conditions.push(condition),
+                None => targets.push((block, vec![condition])),
+            };
+
+        // Consecutive entries with the same target become a single range
+        let mut start = 0;
+        for end in 1..=blocks.len() {
+            if end == blocks.len() || blocks[end] != blocks[start] {
+                let condition = if end - start == 1 {
+                    selector._eq(&constant(start))
+                } else {
+                    z3::ast::Bool::and(
+                        context,
+                        &[
+                            &selector.bvuge(&constant(start)),
+                            &selector.bvult(&constant(end)),
+                        ],
+                    )
+                };
+                add_condition(blocks[start], condition);
+                start = end;
+            }
+        }
+        add_condition(*default, selector.bvuge(&constant(blocks.len())));
+
+        targets
+            .into_iter()
+            .map(|(block, mut conditions)| {
+                let condition = if conditions.len() == 1 {
+                    conditions.pop().unwrap()
+                } else {
+                    z3::ast::Bool::or(context, &conditions.iter().collect::<Vec<_>>())
+                };
+                (block, condition)
+            })
+            .collect()
+    }
+
+    /// Calls the model of an imported function, then resumes after the call instruction
+    fn do_host_call(
+        &self,
+        execution: &mut Execution<'ctx>,
+        func: &walrus::Function,
+        import_id: walrus::ImportId,
+    ) {
+        let import = self.context.module.imports.get(import_id);
+        let ty = self.context.module.types.get(func.ty());
+        let caller = execution.state.call_stack.last_mut().unwrap();
+        let args = caller
+            .value_stack
+            .split_off(caller.value_stack.len() - ty.params().len());
+
+        if let Some(ty) = ty.results().iter().find(|ty| !is_modelled(**ty)) {
+            execution.status = Status::Unsupported(format!(
+                "{} result of {}.{}",
+                ty, import.module, import.name
+            ));
+            return;
+        }
+
+        let key = (import.module.clone(), import.name.clone());
+        let result = match execution.host_models.remove(&key) {
+            Some(mut model) => {
+                let result = model.call(self.context, execution, &args, ty.results());
+                execution.host_models.insert(key, model);
+                result
+            }
+            None => UnconstrainedModel::new(&import.module, &import.name).call(
+                self.context,
+                execution,
+                &args,
+                ty.results(),
+            ),
+        };
+
+        match result {
+            HostResult::Return(values) => {
+                if !values.iter().map(Val::ty).eq(ty.results().iter().copied()) {
+                    warn!(
+                        "Model of {}.{} returned {:?} instead of {:?}",
+                        import.module,
+                        import.name,
+                        values.iter().map(Val::ty).collect::<Vec<_>>(),
+                        ty.results()
+                    );
+                    execution.status =
+                        Status::InvalidHostResult(format!("{}.{}", import.module, import.name));
+                    return;
+                }
+                let caller = execution.state.call_stack.last_mut().unwrap();
+                caller.value_stack.extend(values);
+            }
+            HostResult::Exit => execution.status = Status::Exited,
+            HostResult::Trap(reason) => execution.status = Status::Trap(reason),
+        }
+        execution.advance = true;
+    }
+
+    /// Zeroes the locals of the frame's function that aren't params. Locals of unmodelled types are left out, and
+    /// reading one stops the path.
+    fn init_locals(&self, frame: &mut Frame<'ctx>) {
+        let info = self.info[frame.func.index()].as_ref().unwrap();
+        for local in info.locals.iter() {
+            let local_ty = self.context.module.locals.get(*local).ty();
+            if !frame.locals.contains_key(local) && is_modelled(local_ty) {
+                frame
+                    .locals
+                    .insert(*local, Val::Conc(ConcVal::from_valtype(local_ty)));
+            }
+        }
+    }
+
+    /// Pops the arguments of `func_id` off the current frame and enters the function
+    fn do_call(&self, execution: &mut Execution<'ctx>, func_id: walrus::FunctionId, ret: Loc) {
+        let func = self.context.module.funcs.get(func_id);
+        let local_func = match &func.kind {
+            walrus::FunctionKind::Local(local_func) => local_func,
+            walrus::FunctionKind::Import(import) => {
+                return self.do_host_call(execution, func, import.import);
+            }
+            walrus::FunctionKind::Uninitialized(_) => unreachable!(),
+        };
+        let caller = execution.state.call_stack.last_mut().unwrap();
+        let mut inputs = Vec::new();
+        for _ in local_func.args.iter() {
+            inputs.push(caller.value_stack.pop().unwrap());
+        }
+        inputs.reverse();
+
+        let mut frame = Frame::new(func_id, Some(ret));
+        for (param, value) in std::iter::zip(local_func.args.iter(), inputs) {
+            frame.locals.insert(*param, value);
+        }
+
+        self.init_locals(&mut frame);
+
+        execution.state.call_stack.push(frame);
+        execution.cur_block = local_func.entry_block();
+        execution.cur_location = None;
     }
 
     pub fn step_execution(&mut self, mut execution: Execution<'ctx>) -> Option<Execution<'ctx>> {
+        // Paths can be ended while they are queued, e.g. by a host model
+        if execution.status != Status::None {
+            return Some(execution);
+        }
+
         let frame = execution.state.call_stack.last().unwrap();
         let func_id = frame.func;
         let func = as_local_func(self.context.module.funcs.get(func_id)).unwrap();
@@ -205,8 +890,14 @@ impl<'ctx, 'm> Engine<'ctx, 'm> {
         }
 
         if *execution.hotness.get(&cur_block.id()).unwrap() > self.max_hotness {
-            execution.status = Status::Terminated;
-            return Some(execution);
+            let widened = match self.get_loop_info(&execution) {
+                Some(info) => self.widen_loop(&mut execution, info),
+                None => false,
+            };
+            if !widened {
+                execution.status = Status::Terminated;
+                return Some(execution);
+            }
         }
 
         for (instr, instr_loc) in &cur_block.instrs {
@@ -234,6 +925,30 @@ impl<'ctx, 'm> Engine<'ctx, 'm> {
 
             let frame = execution.state.call_stack.last_mut().unwrap();
             match instr {
+                // Vectors and references aren't modelled, so the path stops at whatever would create one
+                ir::Instr::Const(ir::Const {
+                    value: ir::Value::V128(_),
+                })
+                | ir::Instr::Unop(ir::Unop {
+                    op:
+                        ir::UnaryOp::I8x16Splat
+                        | ir::UnaryOp::I16x8Splat
+                        | ir::UnaryOp::I32x4Splat
+                        | ir::UnaryOp::I64x2Splat
+                        | ir::UnaryOp::F32x4Splat
+                        | ir::UnaryOp::F64x2Splat,
+                })
+                | ir::Instr::Load(ir::Load {
+                    kind: ir::LoadKind::V128,
+                    ..
+                })
+                | ir::Instr::Store(ir::Store {
+                    kind: ir::StoreKind::V128,
+                    ..
+                }) => {
+                    execution.status = Status::Unsupported(format!("{:?}", instr));
+                    return Some(execution);
+                }
                 ir::Instr::Drop(_) => {
                     frame.value_stack.pop().unwrap();
                 }
@@ -265,10 +980,14 @@ impl<'ctx, 'm> Engine<'ctx, 'm> {
                 ir::Instr::Const(imm) => {
                     frame.value_stack.push(Val::Conc(ConcVal(imm.value)));
                 }
-                ir::Instr::LocalGet(imm) => {
-                    let local = frame.locals.get(&imm.local).unwrap();
-                    frame.value_stack.push(local.clone());
-                }
+                ir::Instr::LocalGet(imm) => match frame.locals.get(&imm.local) {
+                    Some(local) => frame.value_stack.push(local.clone()),
+                    None => {
+                        let ty = self.context.module.locals.get(imm.local).ty();
+                        execution.status = Status::Unsupported(format!("{} local", ty));
+                        return Some(execution);
+                    }
+                },
                 ir::Instr::LocalSet(imm) => {
                     let value = frame.value_stack.pop().unwrap();
                     frame.locals.insert(imm.local, value.clone());
@@ -277,7 +996,7 @@ impl<'ctx, 'm> Engine<'ctx, 'm> {
                     let value = frame.value_stack.last().unwrap();
                     frame.locals.insert(imm.local, value.clone());
                 }
-                ir::Instr::Select(imm) => {
+                ir::Instr::Select(_) => {
                     let cond = frame.value_stack.pop().unwrap();
                     let rhs = frame
                         .value_stack
@@ -291,66 +1010,153 @@ impl<'ctx, 'm> Engine<'ctx, 'm> {
                         .as_sym(&self.context.context);
 
                     let sym_cond = cond.as_sym(&self.context.context);
-                    let sym_val = sym_cond
-                        .as_i32()
-                        ._eq(&self.zero(32))
-                        .ite(lhs.as_i32(), rhs.as_i32());
-                    frame.value_stack.push(Val::Sym(SymVal::I32(sym_val)));
-                }
-                // Globals (TODO)
-                ir::Instr::GlobalGet(imm) => {
-                    frame
-                        .value_stack
-                        .push(Val::Conc(ConcVal(ir::Value::I32(0))));
+                    let sym_val = SymVal::ite(&sym_cond.as_i32()._eq(&self.zero(32)), &rhs, &lhs);
+                    frame.value_stack.push(Val::Sym(sym_val));
                 }
+                // Globals
+                ir::Instr::GlobalGet(imm) => match execution.state.globals.get(&imm.global) {
+                    Some(global) => frame.value_stack.push(global.clone()),
+                    None => {
+                        let ty = self.context.module.globals.get(imm.global).ty;
+                        execution.status = Status::Unsupported(format!("{} global", ty));
+                        return Some(execution);
+                    }
+                },
                 ir::Instr::GlobalSet(imm) => {
-                    frame.value_stack.pop();
+                    let value = frame.value_stack.pop().unwrap();
+                    execution.state.globals.insert(imm.global, value);
                 }
                 // Control flow
-                ir::Instr::Block(ir::Block { seq }) | ir::Instr::Loop(ir::Loop { seq }) => {
+                ir::Instr::Block(ir::Block { seq }) => {
                     self.do_jump_to_seq(&mut execution, seq);
                     self.push_execution(execution);
                     return None;
                 }
+                ir::Instr::Loop(ir::Loop { seq }) => {
+                    execution.widened.remove(seq);
+                    self.do_jump_to_seq(&mut execution, seq);
+                    self.enter_loop(execution);
+                    return None;
+                }
                 ir::Instr::Br(imm) => {
                     if self.do_branch(&mut execution, &imm.block) {
-                        break;
+                        return self.do_return(execution);
                     }
                     self.push_execution(execution);
                     return None;
                 }
+                ir::Instr::BrTable(imm) => {
+                    let selector = frame.value_stack.pop().unwrap();
+                    match selector {
+                        Val::Conc(val) => {
+                            let index = val.as_i32() as u32 as usize;
+                            let block = imm.blocks.get(index).unwrap_or(&imm.default);
+                            if self.do_branch(&mut execution, block) {
+                                return self.do_return(execution);
+                            }
+                            self.push_execution(execution);
+                            return None;
+                        }
+                        Val::Sym(val) => {
+                            let selector = val.as_i32();
+                            let targets =
+                                self.get_br_table_targets(selector, &imm.blocks, &imm.default);
+
+                            let mut targets: Vec<_> = targets
+                                .into_iter()
+                                .filter(|(_, condition)| {
+                                    execution.is_feasible(self.context, condition)
+                                })
+                                .collect();
+                            // Without a feasible target there is nothing left of the path
+                            let last = targets.pop()?;
+                            let id = execution.id;
+                            let forks: Vec<_> = targets
+                                .into_iter()
+                                .map(|target| {
+                                    (Execution::from(&mut execution, self.context), target)
+                                })
+                                .collect();
+
+                            // Several targets can leave the function: the first one finished is returned, and the
+                            // others are queued and come back out of the search finished. The path itself takes the
+                            // last target, keeping its solver.
+                            let mut exit_execution = None;
+                            for (mut target_execution, (block, condition)) in
+                                forks.into_iter().chain(std::iter::once((execution, last)))
+                            {
+                                target_execution.add_constraint(condition);
+
+                                trace!(
+                                    "Forking execution #{} on {:?} -> [{:?}: #{}]",
+                                    id,
+                                    selector,
+                                    block,
+                                    target_execution.id
+                                );
+
+                                if !self.do_branch(&mut target_execution, &block) {
+                                    self.push_execution(target_execution);
+                                } else if let Some(finished) = self.do_return(target_execution) {
+                                    match exit_execution {
+                                        None => exit_execution = Some(finished),
+                                        Some(_) => self.push_execution(finished),
+                                    }
+                                }
+                            }
+
+                            return exit_execution;
+                        }
+                    }
+                }
                 ir::Instr::BrIf(imm) => {
                     let condition = frame.value_stack.pop().unwrap();
                     match condition {
                         Val::Conc(val) => {
                             if val.as_i32() != 0 {
                                 if self.do_branch(&mut execution, &imm.block) {
-                                    break;
+                                    return self.do_return(execution);
                                 }
                                 self.push_execution(execution);
                                 return None;
                             }
                         }
                         Val::Sym(val) => {
-                            let mut true_execution = Execution::from(&execution);
-                            true_execution
-                                .constraints
-                                .push(val.as_i32()._eq(&self.zero(32)).not());
-                            if self.do_branch(&mut true_execution, &imm.block) {
-                                break;
-                            }
+                            let is_false = val.as_i32()._eq(&self.zero(32));
+                            let is_true = is_false.not();
+                            match self.get_feasible_sides(&mut execution, &is_true) {
+                                (true, true) => {
+                                    let mut true_execution =
+                                        Execution::from(&mut execution, self.context);
+                                    true_execution.add_constraint(is_true);
+                                    execution.add_constraint(is_false);
 
-                            execution.constraints.push(val.as_i32()._eq(&self.zero(32)));
+                                    trace!(
+                                        "Forking execution #{} on {:?} -> [true: #{}/false: #{}]",
+                                        execution.id,
+                                        val,
+                                        true_execution.id,
+                                        execution.id
+                                    );
 
-                            trace!(
-                                "Forking execution #{} on {:?} -> [true: #{}/false: #{}]",
-                                execution.id,
-                                val,
-                                true_execution.id,
-                                execution.id
-                            );
-
-                            self.push_execution(true_execution);
+                                    if self.do_branch(&mut true_execution, &imm.block) {
+                                        // The false path resumes after the `br_if`
+                                        execution.advance = true;
+                                        self.push_execution(execution);
+                                        return self.do_return(true_execution);
+                                    }
+                                    self.push_execution(true_execution);
+                                }
+                                (true, false) => {
+                                    if self.do_branch(&mut execution, &imm.block) {
+                                        return self.do_return(execution);
+                                    }
+                                    self.push_execution(execution);
+                                    return None;
+                                }
+                                (false, true) => (),
+                                (false, false) => return None,
+                            }
                         }
                     }
                 }
@@ -359,156 +1165,380 @@ impl<'ctx, 'm> Engine<'ctx, 'm> {
                     match condition {
                         Val::Conc(val) => {
                             if val.as_i32() != 0 {
-                                execution.cur_block = imm.consequent;
+                                self.do_jump_to_seq(&mut execution, &imm.consequent);
                             } else {
-                                execution.cur_block = imm.alternative;
+                                self.do_jump_to_seq(&mut execution, &imm.alternative);
                             }
+                            self.push_execution(execution);
+                            return None;
                         }
                         Val::Sym(val) => {
-                            let mut true_execution = Execution::from(&execution);
-                            true_execution
-                                .constraints
-                                .push(val.as_i32()._eq(&self.zero(32)).not());
-                            true_execution.cur_block = imm.consequent;
-                            true_execution.cur_location = None;
-
-                            let mut false_execution = Execution::from(&execution);
-                            false_execution
-                                .constraints
-                                .push(val.as_i32()._eq(&self.zero(32)));
-                            false_execution.cur_block = imm.alternative;
-                            false_execution.cur_location = None;
+                            let is_false = val.as_i32()._eq(&self.zero(32));
+                            let is_true = is_false.not();
+                            match self.get_feasible_sides(&mut execution, &is_true) {
+                                (true, true) => {
+                                    let mut true_execution =
+                                        Execution::from(&mut execution, self.context);
+                                    true_execution.add_constraint(is_true);
+                                    self.do_jump_to_seq(&mut true_execution, &imm.consequent);
 
-                            trace!(
-                                "Forking execution #{} on {:?} -> [true: #{}/false: #{}]",
-                                execution.id,
-                                val,
-                                true_execution.id,
-                                false_execution.id
-                            );
+                                    // The path itself continues on the false side, keeping its solver
+                                    execution.add_constraint(is_false);
+                                    self.do_jump_to_seq(&mut execution, &imm.alternative);
 
-                            self.push_execution(true_execution);
-                            self.push_execution(false_execution);
+                                    trace!(
+                                        "Forking execution #{} on {:?} -> [true: #{}/false: #{}]",
+                                        execution.id,
+                                        val,
+                                        true_execution.id,
+                                        execution.id
+                                    );
+
+                                    self.push_execution(true_execution);
+                                    self.push_execution(execution);
+                                }
+                                (true, false) => {
+                                    self.do_jump_to_seq(&mut execution, &imm.consequent);
+                                    self.push_execution(execution);
+                                }
+                                (false, true) => {
+                                    self.do_jump_to_seq(&mut execution, &imm.alternative);
+                                    self.push_execution(execution);
+                                }
+                                (false, false) => (),
+                            }
                             return None;
                         }
                     }
                 }
                 ir::Instr::Call(imm) => {
-                    let func_id = imm.func;
-                    let func = self.context.module.funcs.get(func_id);
-                    let local_func = as_local_func(func).unwrap();
-                    let mut inputs = Vec::new();
-                    for _ in local_func.args.iter() {
-                        inputs.push(frame.value_stack.pop().unwrap());
-                    }
-                    inputs.reverse();
-
-                    let mut frame = Frame::new(
-                        func_id,
-                        Some(Loc {
-                            block: execution.cur_block,
-                            loc: instr_loc.data(),
-                        }),
-                    );
-                    for (param, value) in std::iter::zip(local_func.args.iter(), inputs.into_iter())
-                    {
-                        frame.locals.insert(*param, value);
-                    }
-
-                    let info = self.info[func_id.index()].as_ref().unwrap();
-                    for local in info.locals.iter() {
-                        if !frame.locals.contains_key(local) {
-                            let local_ty = self.context.module.locals.get(*local).ty();
-                            frame
-                                .locals
-                                .insert(*local, Val::Conc(ConcVal::from_valtype(local_ty)));
-                        }
-                    }
-
-                    execution.state.call_stack.push(frame);
-                    execution.cur_block = local_func.entry_block();
-                    execution.cur_location = None;
+                    let ret = Loc {
+                        block: execution.cur_block,
+                        loc: instr_loc.data(),
+                    };
+                    self.do_call(&mut execution, imm.func, ret);
                     trace!("      -> {}", execution.state);
                     self.push_execution(execution);
                     return None;
                 }
+                ir::Instr::CallIndirect(imm) => {
+                    let index = frame.value_stack.pop().unwrap();
+                    let ret = Loc {
+                        block: execution.cur_block,
+                        loc: instr_loc.data(),
+                    };
+                    let table = execution.state.tables.get(&imm.table).unwrap();
+                    let targets = self.get_call_targets(table, imm.ty);
+
+                    match index {
+                        Val::Conc(val) => {
+                            let index = val.as_i32() as u32 as usize;
+                            match targets.iter().find(|(target, _)| *target == index) {
+                                Some((_, func_id)) => {
+                                    self.do_call(&mut execution, *func_id, ret);
+                                    self.push_execution(execution);
+                                    return None;
+                                }
+                                None => {
+                                    execution.status =
+                                        Status::Trap(TrapReason::InvalidIndirectCall);
+                                    return Some(execution);
+                                }
+                            }
+                        }
+                        Val::Sym(val) => {
+                            let index = val.as_i32();
+                            let mut not_targets = Vec::new();
+                            for (target, func_id) in targets {
+                                let is_target = index._eq(&z3::ast::BV::from_u64(
+                                    &self.context.context,
+                                    target as u64,
+                                    32,
+                                ));
+                                not_targets.push(is_target.not());
+                                if !execution.is_feasible(self.context, &is_target) {
+                                    continue;
+                                }
+
+                                let mut target_execution =
+                                    Execution::from(&mut execution, self.context);
+                                target_execution.add_constraint(is_target);
+                                self.do_call(&mut target_execution, func_id, ret);
+
+                                trace!(
+                                    "Forking execution #{} on {:?} -> [{}: #{}]",
+                                    execution.id,
+                                    index,
+                                    target,
+                                    target_execution.id
+                                );
+
+                                self.push_execution(target_execution);
+                            }
+
+                            // What remains is the path where no compatible function is selected
+                            let no_target = z3::ast::Bool::and(
+                                &self.context.context,
+                                &not_targets.iter().collect::<Vec<_>>(),
+                            );
+                            if !execution.is_feasible(self.context, &no_target) {
+                                return None;
+                            }
+                            execution.add_constraint(no_target);
+                            execution.status = Status::Trap(TrapReason::InvalidIndirectCall);
+                            return Some(execution);
+                        }
+                    }
+                }
                 ir::Instr::Return(_) => {
-                    execution.status = Status::Complete;
+                    return self.do_return(execution);
+                }
+                ir::Instr::Unreachable(_) => {
+                    execution.status = Status::Trap(TrapReason::Unreachable);
                     return Some(execution);
                 }
                 // Memory Instructions
-                ir::Instr::MemorySize(_) => {
-                    let size = execution.state.memory.as_ref().unwrap().size.clone();
+                ir::Instr::MemorySize(imm) => {
+                    let size = execution.state.memories[&imm.memory].size.clone();
                     frame.value_stack.push(size);
                 }
-                ir::Instr::MemoryGrow(_) => {
-                    let size = execution.state.memory.as_ref().unwrap().size.clone();
+                ir::Instr::MemoryGrow(imm) => {
                     let num_pages = frame.value_stack.pop().unwrap();
-                    let new_size = self
-                        .bin_op(ir::BinaryOp::I32Add, &size, &num_pages)
-                        .unwrap();
-                    execution.state.memory.as_mut().unwrap().size = new_size;
-                    frame.value_stack.push(num_pages);
+                    let memory = &execution.state.memories[&imm.memory];
+                    let size = memory.size.clone();
+                    let can_grow = memory.can_grow(self.context, &num_pages).simplify();
+                    let failed = Val::Conc(ConcVal(ir::Value::I32(-1)));
+
+                    let (grows, fails) = self.get_feasible_sides(&mut execution, &can_grow);
+                    if !grows {
+                        let frame = execution.state.call_stack.last_mut().unwrap();
+                        frame.value_stack.push(failed);
+                    } else {
+                        if fails {
+                            let mut failed_execution =
+                                Execution::from(&mut execution, self.context);
+                            failed_execution.add_constraint(can_grow.not());
+                            let frame = failed_execution.state.call_stack.last_mut().unwrap();
+                            frame.value_stack.push(failed);
+                            failed_execution.advance = true;
+
+                            trace!(
+                                "Forking execution #{} on memory.grow -> [grown: #{}/failed: #{}]",
+                                execution.id,
+                                execution.id,
+                                failed_execution.id
+                            );
+
+                            self.push_execution(failed_execution);
+                            execution.add_constraint(can_grow);
+                        }
+
+                        let new_size = self
+                            .bin_op(ir::BinaryOp::I32Add, &size, &num_pages)
+                            .unwrap();
+                        execution.state.memories.get_mut(&imm.memory).unwrap().size = new_size;
+                        let frame = execution.state.call_stack.last_mut().unwrap();
+                        frame.value_stack.push(size);
+                    }
+                }
+                ir::Instr::MemoryFill(imm) => {
+                    let len = frame.value_stack.pop().unwrap();
+                    let value = frame.value_stack.pop().unwrap();
+                    let dest = frame.value_stack.pop().unwrap();
+                    let in_bounds = self.get_in_bounds(&execution, imm.memory, &dest, 0, &len);
+                    if !self.do_bounds_check(&mut execution, in_bounds) {
+                        return Some(execution);
+                    }
+
+                    let memory = execution.state.memories.get_mut(&imm.memory).unwrap();
+                    memory.fill(self.context, &dest, &value, &len);
+                }
+                ir::Instr::MemoryCopy(imm) => {
+                    let len = frame.value_stack.pop().unwrap();
+                    let src = frame.value_stack.pop().unwrap();
+                    let dest = frame.value_stack.pop().unwrap();
+                    let in_bounds = z3::ast::Bool::and(
+                        &self.context.context,
+                        &[
+                            &self.get_in_bounds(&execution, imm.dst, &dest, 0, &len),
+                            &self.get_in_bounds(&execution, imm.src, &src, 0, &len),
+                        ],
+                    );
+                    if !self.do_bounds_check(&mut execution, in_bounds) {
+                        return Some(execution);
+                    }
+
+                    let source = execution.state.memories[&imm.src].clone();
+                    let memory = execution.state.memories.get_mut(&imm.dst).unwrap();
+                    memory.copy(self.context, &dest, &source, &src, &len);
+                }
+                ir::Instr::MemoryInit(imm) => {
+                    let len = frame.value_stack.pop().unwrap();
+                    let src = frame.value_stack.pop().unwrap();
+                    let dest = frame.value_stack.pop().unwrap();
+                    let data = execution.state.data.get(&imm.data).unwrap().clone();
+
+                    // Dropped segments are empty, so any non-empty init from them traps
+                    let context = &self.context.context;
+                    let end = src
+                        .as_sym(context)
+                        .as_i32()
+                        .zero_ext(32)
+                        .bvadd(&len.as_sym(context).as_i32().zero_ext(32));
+                    let in_data = end.bvule(&z3::ast::BV::from_u64(context, data.len() as u64, 64));
+                    let in_bounds = z3::ast::Bool::and(
+                        context,
+                        &[
+                            &in_data,
+                            &self.get_in_bounds(&execution, imm.memory, &dest, 0, &len),
+                        ],
+                    );
+                    if !self.do_bounds_check(&mut execution, in_bounds) {
+                        return Some(execution);
+                    }
+
+                    let memory = execution.state.memories.get_mut(&imm.memory).unwrap();
+                    memory.init(self.context, &dest, &src, &len, &data);
+                }
+                ir::Instr::DataDrop(imm) => {
+                    execution.state.data.insert(imm.data, Rc::from(Vec::new()));
                 }
                 ir::Instr::Load(imm) => {
-                    let memory = execution.state.memory.as_ref().unwrap();
-                    let offset = imm.arg.offset as i32;
+                    let offset = imm.arg.offset;
                     let index = frame.value_stack.pop().unwrap();
-                    let access_index = self
-                        .bin_op(
-                            ir::BinaryOp::I32Add,
-                            &Val::Conc(ConcVal(ir::Value::I32(offset))),
-                            &index,
-                        )
-                        .unwrap();
+                    let width = Val::Conc(ConcVal(ir::Value::I32(imm.kind.width() as i32)));
+                    let in_bounds =
+                        self.get_in_bounds(&execution, imm.memory, &index, offset, &width);
+                    if !self.do_bounds_check(&mut execution, in_bounds)
+                        || imm.kind.atomic()
+                            && !self.do_alignment_check(
+                                &mut execution,
+                                &index,
+                                offset,
+                                imm.kind.width(),
+                            )
+                    {
+                        return Some(execution);
+                    }
+
+                    let memory = &execution.state.memories[&imm.memory];
 
                     let value = match imm.kind {
                         ir::LoadKind::I32 { .. } => {
-                            self.do_load(&memory, &access_index, 32, 32, false)
+                            self.do_load(memory, &index, offset, 32, 32, false)
                         }
                         ir::LoadKind::I32_8 {
-                            kind: ir::ExtendedLoad::ZeroExtend,
-                        } => self.do_load(&memory, &access_index, 8, 32, true),
+                            kind: ir::ExtendedLoad::ZeroExtend | ir::ExtendedLoad::ZeroExtendAtomic,
+                        } => self.do_load(memory, &index, offset, 8, 32, true),
                         ir::LoadKind::I32_8 { .. } => {
-                            self.do_load(&memory, &access_index, 8, 32, false)
+                            self.do_load(memory, &index, offset, 8, 32, false)
                         }
                         ir::LoadKind::I32_16 {
-                            kind: ir::ExtendedLoad::ZeroExtend,
-                        } => self.do_load(&memory, &access_index, 16, 32, true),
+                            kind: ir::ExtendedLoad::ZeroExtend | ir::ExtendedLoad::ZeroExtendAtomic,
+                        } => self.do_load(memory, &index, offset, 16, 32, true),
                         ir::LoadKind::I32_16 { .. } => {
-                            self.do_load(&memory, &access_index, 16, 32, false)
+                            self.do_load(memory, &index, offset, 16, 32, false)
                         }
-                        _ => unimplemented!(),
+                        ir::LoadKind::I64 { .. } => {
+                            self.do_load(memory, &index, offset, 64, 64, false)
+                        }
+                        ir::LoadKind::I64_8 {
+                            kind: ir::ExtendedLoad::ZeroExtend | ir::ExtendedLoad::ZeroExtendAtomic,
+                        } => self.do_load(memory, &index, offset, 8, 64, true),
+                        ir::LoadKind::I64_8 { .. } => {
+                            self.do_load(memory, &index, offset, 8, 64, false)
+                        }
+                        ir::LoadKind::I64_16 {
+                            kind: ir::ExtendedLoad::ZeroExtend | ir::ExtendedLoad::ZeroExtendAtomic,
+                        } => self.do_load(memory, &index, offset, 16, 64, true),
+                        ir::LoadKind::I64_16 { .. } => {
+                            self.do_load(memory, &index, offset, 16, 64, false)
+                        }
+                        ir::LoadKind::I64_32 {
+                            kind: ir::ExtendedLoad::ZeroExtend | ir::ExtendedLoad::ZeroExtendAtomic,
+                        } => self.do_load(memory, &index, offset, 32, 64, true),
+                        ir::LoadKind::I64_32 { .. } => {
+                            self.do_load(memory, &index, offset, 32, 64, false)
+                        }
+                        ir::LoadKind::F32 => self
+                            .un_op(
+                                ir::UnaryOp::F32ReinterpretI32,
+                                &self.do_load(memory, &index, offset, 32, 32, false),
+                            )
+                            .unwrap(),
+                        ir::LoadKind::F64 => self
+                            .un_op(
+                                ir::UnaryOp::F64ReinterpretI64,
+                                &self.do_load(memory, &index, offset, 64, 64, false),
+                            )
+                            .unwrap(),
+                        ir::LoadKind::V128 => unreachable!(),
                     };
+                    let frame = execution.state.call_stack.last_mut().unwrap();
                     frame.value_stack.push(value);
                 }
                 ir::Instr::Store(imm) => {
-                    let memory = execution.state.memory.as_mut().unwrap();
-                    let offset = imm.arg.offset as i32;
+                    let offset = imm.arg.offset;
                     let value = frame.value_stack.pop().unwrap();
                     let index = frame.value_stack.pop().unwrap();
-                    let access_index = self
-                        .bin_op(
-                            ir::BinaryOp::I32Add,
-                            &Val::Conc(ConcVal(ir::Value::I32(offset))),
-                            &index,
-                        )
-                        .unwrap();
+                    let width = Val::Conc(ConcVal(ir::Value::I32(imm.kind.width() as i32)));
+                    let in_bounds =
+                        self.get_in_bounds(&execution, imm.memory, &index, offset, &width);
+                    if !self.do_bounds_check(&mut execution, in_bounds)
+                        || imm.kind.atomic()
+                            && !self.do_alignment_check(
+                                &mut execution,
+                                &index,
+                                offset,
+                                imm.kind.width(),
+                            )
+                    {
+                        return Some(execution);
+                    }
+
+                    let memory = execution.state.memories.get_mut(&imm.memory).unwrap();
 
                     match imm.kind {
                         ir::StoreKind::I32 { .. } => {
-                            self.do_store(memory, &access_index, value, 32)
+                            self.do_store(memory, &index, offset, value, 32)
                         }
                         ir::StoreKind::I32_8 { .. } => {
-                            self.do_store(memory, &access_index, value, 8)
+                            self.do_store(memory, &index, offset, value, 8)
                         }
                         ir::StoreKind::I32_16 { .. } => {
-                            self.do_store(memory, &access_index, value, 16)
+                            self.do_store(memory, &index, offset, value, 16)
+                        }
+                        ir::StoreKind::I64 { .. } => {
+                            self.do_store(memory, &index, offset, value, 64)
+                        }
+                        ir::StoreKind::I64_8 { .. } => {
+                            self.do_store(memory, &index, offset, value, 8)
+                        }
+                        ir::StoreKind::I64_16 { .. } => {
+                            self.do_store(memory, &index, offset, value, 16)
+                        }
+                        ir::StoreKind::I64_32 { .. } => {
+                            self.do_store(memory, &index, offset, value, 32)
                         }
-                        _ => unimplemented!(),
+                        ir::StoreKind::F32 => self.do_store(memory, &index, offset, value, 32),
+                        ir::StoreKind::F64 => self.do_store(memory, &index, offset, value, 64),
+                        ir::StoreKind::V128 => unreachable!(),
                     }
                 }
-                _ => unimplemented!(),
+                ir::Instr::AtomicRmw(_)
+                | ir::Instr::Cmpxchg(_)
+                | ir::Instr::AtomicNotify(_)
+                | ir::Instr::AtomicWait(_) => {
+                    if !self.do_atomic(&mut execution, instr) {
+                        return Some(execution);
+                    }
+                }
+                // Executions are single-threaded, so there is nothing to order
+                ir::Instr::AtomicFence(_) => (),
+                _ => {
+                    execution.status = Status::Unsupported(format!("{:?}", instr));
+                    return Some(execution);
+                }
             }
 
             execution.state.simplify();
@@ -518,25 +1548,7 @@ impl<'ctx, 'm> Engine<'ctx, 'm> {
         execution.advance = false;
         let info = self.info[func_id.index()].as_ref().unwrap();
         match info.ends.get(&cur_block.id()) {
-            None => {
-                let old_frame = execution.state.call_stack.pop().unwrap();
-                match execution.state.call_stack.last_mut() {
-                    None => {
-                        execution.status = Status::Complete;
-                        execution.state.call_stack.push(old_frame);
-                        Some(execution)
-                    }
-                    Some(prev_frame) => {
-                        let ret = old_frame.ret.unwrap();
-                        execution.cur_block = ret.block;
-                        execution.cur_location = Some(InstrLocId::new(ret.loc));
-                        execution.advance = true;
-                        prev_frame.value_stack.extend(old_frame.value_stack);
-                        self.push_execution(execution);
-                        return None;
-                    }
-                }
-            }
+            None => self.do_return(execution),
             Some(end) => {
                 execution.cur_block = end.block;
                 execution.cur_location = Some(ir::InstrLocId::new(end.loc));
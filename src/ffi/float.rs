@@ -0,0 +1,278 @@
+//! Floating-point terms missing from the `z3` crate's `Float` API, built directly on `z3_sys`.
+//!
+//! All arithmetic uses round-to-nearest-ties-to-even, as required by the wasm spec.
+use super::raw_context;
+use z3::ast::{Ast, Bool, Float, BV};
+use z3_sys::*;
+
+fn raw_sort<'ctx>(context: &z3::Context, value: &impl Ast<'ctx>) -> Z3_sort {
+    unsafe { Z3_get_sort(raw_context(context), value.get_z3_ast()) }
+}
+
+pub fn from_f64<'ctx>(context: &'ctx z3::Context, value: f64, size: u32) -> Float<'ctx> {
+    match size {
+        32 => Float::from_f32(context, value as f32),
+        64 => Float::from_f64(context, value),
+        _ => unreachable!(),
+    }
+}
+
+/// Width in bits of a float term, either 32 or 64
+pub fn size(value: &Float) -> u32 {
+    let context = value.get_ctx();
+    let ctx = raw_context(context);
+    let sort = raw_sort(context, value);
+    unsafe { Z3_fpa_get_ebits(ctx, sort) + Z3_fpa_get_sbits(ctx, sort) }
+}
+
+fn rounding_mode<'ctx>(context: &'ctx z3::Context, rounding: Rounding) -> Float<'ctx> {
+    let ctx = raw_context(context);
+    // Rounding modes have their own sort, but the `z3` crate represents them as `Float`
+    Float::new(context, unsafe {
+        match rounding {
+            Rounding::Nearest => Z3_mk_fpa_rne(ctx),
+            Rounding::Ceil => Z3_mk_fpa_rtp(ctx),
+            Rounding::Floor => Z3_mk_fpa_rtn(ctx),
+            Rounding::Trunc => Z3_mk_fpa_rtz(ctx),
+        }
+    })
+}
+
+fn float_op<'ctx>(
+    lhs: &Float<'ctx>,
+    rhs: &Float<'ctx>,
+    op: unsafe extern "C" fn(Z3_context, Z3_ast, Z3_ast, Z3_ast) -> Z3_ast,
+) -> Float<'ctx> {
+    let context = lhs.get_ctx();
+    let rm = rounding_mode(context, Rounding::Nearest);
+    Float::new(context, unsafe {
+        op(
+            raw_context(context),
+            rm.get_z3_ast(),
+            lhs.get_z3_ast(),
+            rhs.get_z3_ast(),
+        )
+    })
+}
+
+fn float_binop<'ctx, T: Ast<'ctx>>(
+    lhs: &Float<'ctx>,
+    rhs: &Float<'ctx>,
+    op: unsafe extern "C" fn(Z3_context, Z3_ast, Z3_ast) -> Z3_ast,
+) -> T {
+    let context = lhs.get_ctx();
+    T::new(context, unsafe {
+        op(raw_context(context), lhs.get_z3_ast(), rhs.get_z3_ast())
+    })
+}
+
+fn float_test<'ctx>(
+    value: &Float<'ctx>,
+    op: unsafe extern "C" fn(Z3_context, Z3_ast) -> Z3_ast,
+) -> Bool<'ctx> {
+    let context = value.get_ctx();
+    Bool::new(context, unsafe {
+        op(raw_context(context), value.get_z3_ast())
+    })
+}
+
+pub fn nan<'ctx>(context: &'ctx z3::Context, size: u32) -> Float<'ctx> {
+    from_f64(context, f64::NAN, size)
+}
+
+pub fn add<'ctx>(lhs: &Float<'ctx>, rhs: &Float<'ctx>) -> Float<'ctx> {
+    float_op(lhs, rhs, Z3_mk_fpa_add)
+}
+
+pub fn sub<'ctx>(lhs: &Float<'ctx>, rhs: &Float<'ctx>) -> Float<'ctx> {
+    float_op(lhs, rhs, Z3_mk_fpa_sub)
+}
+
+pub fn mul<'ctx>(lhs: &Float<'ctx>, rhs: &Float<'ctx>) -> Float<'ctx> {
+    float_op(lhs, rhs, Z3_mk_fpa_mul)
+}
+
+pub fn div<'ctx>(lhs: &Float<'ctx>, rhs: &Float<'ctx>) -> Float<'ctx> {
+    float_op(lhs, rhs, Z3_mk_fpa_div)
+}
+
+pub fn eq<'ctx>(lhs: &Float<'ctx>, rhs: &Float<'ctx>) -> Bool<'ctx> {
+    float_binop(lhs, rhs, Z3_mk_fpa_eq)
+}
+
+pub fn lt<'ctx>(lhs: &Float<'ctx>, rhs: &Float<'ctx>) -> Bool<'ctx> {
+    float_binop(lhs, rhs, Z3_mk_fpa_lt)
+}
+
+pub fn le<'ctx>(lhs: &Float<'ctx>, rhs: &Float<'ctx>) -> Bool<'ctx> {
+    float_binop(lhs, rhs, Z3_mk_fpa_leq)
+}
+
+pub fn gt<'ctx>(lhs: &Float<'ctx>, rhs: &Float<'ctx>) -> Bool<'ctx> {
+    float_binop(lhs, rhs, Z3_mk_fpa_gt)
+}
+
+pub fn ge<'ctx>(lhs: &Float<'ctx>, rhs: &Float<'ctx>) -> Bool<'ctx> {
+    float_binop(lhs, rhs, Z3_mk_fpa_geq)
+}
+
+pub fn is_nan<'ctx>(value: &Float<'ctx>) -> Bool<'ctx> {
+    float_test(value, Z3_mk_fpa_is_nan)
+}
+
+pub fn is_zero<'ctx>(value: &Float<'ctx>) -> Bool<'ctx> {
+    float_test(value, Z3_mk_fpa_is_zero)
+}
+
+pub fn is_negative<'ctx>(value: &Float<'ctx>) -> Bool<'ctx> {
+    float_test(value, Z3_mk_fpa_is_negative)
+}
+
+/// wasm `min`: NaN if either operand is NaN, and -0 is less than +0
+pub fn min<'ctx>(lhs: &Float<'ctx>, rhs: &Float<'ctx>) -> Float<'ctx> {
+    let context = lhs.get_ctx();
+    let min: Float = float_binop(lhs, rhs, Z3_mk_fpa_min);
+    let zeros = Bool::and(context, &[&is_zero(lhs), &is_zero(rhs)]);
+    let nans = Bool::or(context, &[&is_nan(lhs), &is_nan(rhs)]);
+    nans.ite(
+        &nan(context, size(lhs)),
+        &zeros.ite(&is_negative(lhs).ite(lhs, rhs), &min),
+    )
+}
+
+/// wasm `max`: NaN if either operand is NaN, and +0 is greater than -0
+pub fn max<'ctx>(lhs: &Float<'ctx>, rhs: &Float<'ctx>) -> Float<'ctx> {
+    let context = lhs.get_ctx();
+    let max: Float = float_binop(lhs, rhs, Z3_mk_fpa_max);
+    let zeros = Bool::and(context, &[&is_zero(lhs), &is_zero(rhs)]);
+    let nans = Bool::or(context, &[&is_nan(lhs), &is_nan(rhs)]);
+    nans.ite(
+        &nan(context, size(lhs)),
+        &zeros.ite(&is_negative(lhs).ite(rhs, lhs), &max),
+    )
+}
+
+pub fn abs<'ctx>(value: &Float<'ctx>) -> Float<'ctx> {
+    value.unary_abs()
+}
+
+pub fn neg<'ctx>(value: &Float<'ctx>) -> Float<'ctx> {
+    value.unary_neg()
+}
+
+pub fn copysign<'ctx>(lhs: &Float<'ctx>, rhs: &Float<'ctx>) -> Float<'ctx> {
+    let abs = abs(lhs);
+    is_negative(rhs).ite(&neg(&abs), &abs)
+}
+
+pub fn sqrt<'ctx>(value: &Float<'ctx>) -> Float<'ctx> {
+    let context = value.get_ctx();
+    let rm = rounding_mode(context, Rounding::Nearest);
+    Float::new(context, unsafe {
+        Z3_mk_fpa_sqrt(raw_context(context), rm.get_z3_ast(), value.get_z3_ast())
+    })
+}
+
+/// Rounding modes used by the wasm rounding instructions
+#[derive(Debug, Clone, Copy)]
+pub enum Rounding {
+    Nearest,
+    Ceil,
+    Floor,
+    Trunc,
+}
+
+pub fn round<'ctx>(value: &Float<'ctx>, rounding: Rounding) -> Float<'ctx> {
+    let context = value.get_ctx();
+    let rm = rounding_mode(context, rounding);
+    Float::new(context, unsafe {
+        Z3_mk_fpa_round_to_integral(raw_context(context), rm.get_z3_ast(), value.get_z3_ast())
+    })
+}
+
+/// Reinterprets the bits of an IEEE-754 float as a bitvector
+pub fn to_bits<'ctx>(value: &Float<'ctx>) -> BV<'ctx> {
+    let context = value.get_ctx();
+    BV::new(context, unsafe {
+        Z3_mk_fpa_to_ieee_bv(raw_context(context), value.get_z3_ast())
+    })
+}
+
+/// Reinterprets a 32- or 64-bit bitvector as an IEEE-754 float
+pub fn from_bits<'ctx>(value: &BV<'ctx>) -> Float<'ctx> {
+    let context = value.get_ctx();
+    let template = from_f64(context, 0.0, value.get_size());
+    Float::new(context, unsafe {
+        Z3_mk_fpa_to_fp_bv(
+            raw_context(context),
+            value.get_z3_ast(),
+            raw_sort(context, &template),
+        )
+    })
+}
+
+/// Truncates a float towards zero into a `size`-bit integer; out-of-range results are unspecified
+pub fn to_int<'ctx>(value: &Float<'ctx>, size: u32, signed: bool) -> BV<'ctx> {
+    let context = value.get_ctx();
+    let rm = rounding_mode(context, Rounding::Trunc);
+    BV::new(context, unsafe {
+        let convert = if signed {
+            Z3_mk_fpa_to_sbv
+        } else {
+            Z3_mk_fpa_to_ubv
+        };
+        convert(
+            raw_context(context),
+            rm.get_z3_ast(),
+            value.get_z3_ast(),
+            size,
+        )
+    })
+}
+
+/// Converts an integer into a `size`-bit float, rounding to nearest
+pub fn from_int<'ctx>(value: &BV<'ctx>, size: u32, signed: bool) -> Float<'ctx> {
+    let context = value.get_ctx();
+    let rm = rounding_mode(context, Rounding::Nearest);
+    let template = from_f64(context, 0.0, size);
+    Float::new(context, unsafe {
+        let convert = if signed {
+            Z3_mk_fpa_to_fp_signed
+        } else {
+            Z3_mk_fpa_to_fp_unsigned
+        };
+        convert(
+            raw_context(context),
+            rm.get_z3_ast(),
+            value.get_z3_ast(),
+            raw_sort(context, &template),
+        )
+    })
+}
+
+/// Converts a float into a `size`-bit float, rounding to nearest
+pub fn convert<'ctx>(value: &Float<'ctx>, size: u32) -> Float<'ctx> {
+    let context = value.get_ctx();
+    let rm = rounding_mode(context, Rounding::Nearest);
+    let template = from_f64(context, 0.0, size);
+    Float::new(context, unsafe {
+        Z3_mk_fpa_to_fp_float(
+            raw_context(context),
+            rm.get_z3_ast(),
+            value.get_z3_ast(),
+            raw_sort(context, &template),
+        )
+    })
+}
+
+/// Evaluates a float in a model, returning its value as an `f64`
+pub fn eval(model: &z3::Model, value: &Float) -> f64 {
+    if model.eval(&is_nan(value), true).unwrap().as_bool() == Some(true) {
+        return f64::NAN;
+    }
+    let bits = model.eval(&to_bits(value), true).unwrap().as_u64().unwrap();
+    match size(value) {
+        32 => f32::from_bits(bits as u32) as f64,
+        _ => f64::from_bits(bits),
+    }
+}
@@ -0,0 +1,29 @@
+//! Everything that goes through `z3_sys` directly instead of the `z3` crate's safe API.
+//!
+//! The `z3` crate doesn't expose the raw `Z3_context` behind a `z3::Context`, so `raw_context` reads
+//! it out of the struct. This relies on the private layout of `z3::Context` 0.11.2, a struct holding
+//! nothing but that pointer, which is why Cargo.toml pins `z3` and `z3-sys` to exact versions.
+//! Check that the layout still matches before bumping either of them.
+use z3::ast::{Array, Ast, BV};
+use z3_sys::*;
+
+pub mod float;
+
+// `z3::Context` must be exactly one `Z3_context` for `raw_context` to be sound
+const _: () = assert!(std::mem::size_of::<z3::Context>() == std::mem::size_of::<Z3_context>());
+const _: () = assert!(std::mem::align_of::<z3::Context>() == std::mem::align_of::<Z3_context>());
+
+pub(crate) fn raw_context(context: &z3::Context) -> Z3_context {
+    // The assertions above guarantee that the only field, the raw pointer, sits at offset 0
+    unsafe { *(context as *const z3::Context as *const Z3_context) }
+}
+
+/// Array mapping each index `i` to `body`, which refers to `i` through `bound`
+pub(crate) fn lambda<'ctx>(bound: &BV<'ctx>, body: &BV<'ctx>) -> Array<'ctx> {
+    let context = bound.get_ctx();
+    let ctx = raw_context(context);
+    Array::new(context, unsafe {
+        let bound = Z3_to_app(ctx, bound.get_z3_ast());
+        Z3_mk_lambda_const(ctx, 1, &bound, body.get_z3_ast())
+    })
+}
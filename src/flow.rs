@@ -1,3 +1,4 @@
+use crate::loops::{analyze_loop, LoopInfo};
 use std::collections::{HashMap, HashSet};
 use walrus::ir;
 
@@ -20,12 +21,18 @@ pub struct Info {
     seqs: Vec<ir::InstrSeqId>,
     pub types: HashMap<ir::InstrSeqId, ir::Instr>,
     pub ends: HashMap<ir::InstrSeqId, Loc>,
+    pub parents: HashMap<ir::InstrSeqId, ir::InstrSeqId>, // enclosing sequence of each nested one
+    pub joins: HashSet<u32>, // locations execution continues at after leaving a block
     pub locals: HashSet<ir::LocalId>,
+    pub loops: HashMap<ir::InstrSeqId, LoopInfo>,
 }
 
 impl<'instr, 'a> ir::Visitor<'instr> for InfoVisitor<'a> {
     fn start_instr_seq(&mut self, instr_seq: &'instr ir::InstrSeq) {
         self.info.seqs.push(instr_seq.id());
+        if let Some(parent) = self.blocks.last() {
+            self.info.parents.insert(instr_seq.id(), *parent);
+        }
         self.to_update_next.push(Vec::new());
         self.blocks.push(instr_seq.id());
     }
@@ -66,6 +73,12 @@ impl<'instr, 'a> ir::Visitor<'instr> for InfoVisitor<'a> {
             .insert(imm.seq, ir::Instr::Loop(imm.clone()));
     }
 
+    fn visit_if_else(&mut self, imm: &ir::IfElse) {
+        for seq in [imm.consequent, imm.alternative] {
+            self.info.types.insert(seq, ir::Instr::IfElse(imm.clone()));
+        }
+    }
+
     fn visit_local_id(&mut self, id: &ir::LocalId) {
         self.info.locals.insert(*id);
     }
@@ -76,8 +89,11 @@ pub fn compute_info(func: &walrus::LocalFunction) -> Info {
         locs: Vec::new(),
         seqs: Vec::new(),
         ends: HashMap::new(),
+        parents: HashMap::new(),
+        joins: HashSet::new(),
         types: HashMap::new(),
         locals: HashSet::new(),
+        loops: HashMap::new(),
     };
     let mut info_visitor = InfoVisitor {
         info: &mut info,
@@ -86,5 +102,12 @@ pub fn compute_info(func: &walrus::LocalFunction) -> Info {
     };
 
     ir::dfs_in_order(&mut info_visitor, func, func.entry_block());
+
+    info.joins = info.ends.values().map(|end| end.loc).collect();
+    for (seq, instr) in &info.types {
+        if let ir::Instr::Loop(_) = instr {
+            info.loops.insert(*seq, analyze_loop(func, *seq));
+        }
+    }
     info
 }
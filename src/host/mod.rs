@@ -0,0 +1,89 @@
+use crate::context::Context;
+use crate::state::{Execution, TrapReason};
+use crate::value::{SymVal, Val};
+use dyn_clone::DynClone;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+mod wasi;
+
+pub use wasi::*;
+
+pub enum HostResult<'ctx> {
+    /// Values pushed onto the caller's stack, one per declared result
+    Return(Vec<Val<'ctx>>),
+    /// Ends the path as if the entry function had returned
+    Exit,
+    Trap(TrapReason),
+}
+
+/// Model of an imported host function, registered on the engine for a (module, name) pair.
+///
+/// Every execution carries its own copy of each model, so any state a model keeps forks along with the path.
+pub trait HostModel<'ctx>: DynClone + std::fmt::Debug {
+    fn call(
+        &mut self,
+        context: &'ctx Context,
+        execution: &mut Execution<'ctx>,
+        args: &[Val<'ctx>],
+        results: &[walrus::ValType],
+    ) -> HostResult<'ctx>;
+
+    /// How far the model has consumed its input, the only state a model keeps between calls
+    fn position(&self) -> usize {
+        0
+    }
+
+    /// Whether `other`, a copy of this model on another path, is in the same state
+    fn same_state(&self, other: &dyn HostModel<'ctx>) -> bool {
+        self.position() == other.position()
+    }
+}
+
+impl<'ctx> Clone for Box<dyn HostModel<'ctx> + 'ctx> {
+    fn clone(&self) -> Self {
+        dyn_clone::clone_box(&**self)
+    }
+}
+
+static FRESH_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Creates a symbolic value that is distinct from every other one created so far
+pub fn fresh_value<'ctx>(context: &'ctx Context, ty: walrus::ValType, prefix: &str) -> Val<'ctx> {
+    let id = FRESH_COUNTER.fetch_add(1, Ordering::SeqCst);
+    Val::Sym(SymVal::from_valtype(
+        &context.context,
+        ty,
+        format!("{}!{}", prefix, id),
+    ))
+}
+
+/// Fallback for imports without a registered model, returning unconstrained values
+#[derive(Clone, Debug)]
+pub struct UnconstrainedModel {
+    name: String,
+}
+
+impl UnconstrainedModel {
+    pub fn new(module: &str, name: &str) -> Self {
+        UnconstrainedModel {
+            name: format!("{}.{}", module, name),
+        }
+    }
+}
+
+impl<'ctx> HostModel<'ctx> for UnconstrainedModel {
+    fn call(
+        &mut self,
+        context: &'ctx Context,
+        _: &mut Execution<'ctx>,
+        _: &[Val<'ctx>],
+        results: &[walrus::ValType],
+    ) -> HostResult<'ctx> {
+        HostResult::Return(
+            results
+                .iter()
+                .map(|ty| fresh_value(context, *ty, &self.name))
+                .collect(),
+        )
+    }
+}
@@ -0,0 +1,529 @@
+//! Models of the `wasi_snapshot_preview1` functions used by most `wasm32-wasi` programs.
+//!
+//! Buffers passed through iovecs must have concrete addresses and lengths; calls with symbolic ones fail with `EINVAL`.
+//! Calls that would touch memory out of bounds fail with `EFAULT` before writing anything, and calls from a module
+//! without a memory fail with `ENOSYS`.
+use crate::context::Context;
+use crate::engine::Engine;
+use crate::host::{fresh_value, HostModel, HostResult};
+use crate::memory::Memory;
+use crate::state::Execution;
+use crate::value::{ConcVal, SymVal, Val};
+use log::warn;
+use std::rc::Rc;
+use walrus::ir;
+use z3::ast::{Ast, BV};
+
+pub const WASI_MODULE: &str = "wasi_snapshot_preview1";
+
+const ERRNO_SUCCESS: i32 = 0;
+const ERRNO_BADF: i32 = 8;
+const ERRNO_FAULT: i32 = 21;
+const ERRNO_INVAL: i32 = 28;
+const ERRNO_NOSYS: i32 = 52;
+
+const STDIN: u32 = 0;
+const STDOUT: u32 = 1;
+const STDERR: u32 = 2;
+
+/// Contents of the process environment seen by the program
+#[derive(Clone, Debug, Default)]
+pub struct WasiConfig<'ctx> {
+    pub stdin: Vec<BV<'ctx>>,
+    pub args: Vec<Vec<BV<'ctx>>>,
+    pub env: Vec<Vec<BV<'ctx>>>, // `KEY=VALUE` entries
+}
+
+impl<'ctx> WasiConfig<'ctx> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn symbolic_bytes(context: &'ctx Context, name: &str, len: usize) -> Vec<BV<'ctx>> {
+        (0..len)
+            .map(|i| BV::new_const(&context.context, format!("{}[{}]", name, i), 8))
+            .collect()
+    }
+
+    pub fn concrete_bytes(context: &'ctx Context, bytes: &[u8]) -> Vec<BV<'ctx>> {
+        bytes
+            .iter()
+            .map(|byte| BV::from_u64(&context.context, *byte as u64, 8))
+            .collect()
+    }
+}
+
+fn errno(value: i32) -> HostResult<'static> {
+    HostResult::Return(vec![Val::Conc(ConcVal(ir::Value::I32(value)))])
+}
+
+fn pointer<'ctx>(address: u32) -> Val<'ctx> {
+    Val::Conc(ConcVal(ir::Value::I32(address as i32)))
+}
+
+/// Memory that WASI pointers refer to: the exported `memory`, or else the module's first one
+fn guest_memory(context: &Context) -> Option<walrus::MemoryId> {
+    let module = &context.module;
+    module
+        .exports
+        .iter()
+        .find_map(|export| match export.item {
+            walrus::ExportItem::Memory(id) if export.name == "memory" => Some(id),
+            _ => None,
+        })
+        .or_else(|| module.memories.iter().next().map(|memory| memory.id()))
+}
+
+/// Whether any of the `len` bytes at `address` can lie outside the memory. A symbolic address that only might is
+/// counted as faulting, as buffers are expected to be concrete.
+fn faults<'ctx>(
+    context: &'ctx Context,
+    execution: &mut Execution<'ctx>,
+    memory: walrus::MemoryId,
+    address: &Val<'ctx>,
+    len: u32,
+) -> bool {
+    let address = address.as_sym(&context.context).as_i32().zero_ext(32);
+    let len = Val::Conc(ConcVal(ir::Value::I32(len as i32)));
+    let out_of_bounds = execution.state.memories[&memory].out_of_bounds(context, &address, &len);
+    execution.is_feasible(context, &out_of_bounds)
+}
+
+/// Value of an `i32` argument if it is known, used for lengths and buffer addresses
+fn concrete(value: &Val) -> Option<u32> {
+    match value {
+        Val::Conc(val) => Some(val.as_i32() as u32),
+        Val::Sym(val) => val.as_i32().simplify().as_u64().map(|v| v as u32),
+    }
+}
+
+fn write_u32<'ctx>(
+    context: &'ctx Context,
+    memory: &mut Memory<'ctx>,
+    address: &Val<'ctx>,
+    value: u32,
+) {
+    let value = BV::from_u64(&context.context, value as u64, 32);
+    memory.write(context, address, &value);
+}
+
+/// Reads the (buffer, length) pairs of an iovec array, or the errno the call fails with
+fn read_iovecs<'ctx>(
+    context: &'ctx Context,
+    execution: &mut Execution<'ctx>,
+    memory: walrus::MemoryId,
+    iovs: &Val<'ctx>,
+    iovs_len: &Val<'ctx>,
+) -> Result<Vec<(u32, u32)>, i32> {
+    let (iovs, iovs_len) = match (concrete(iovs), concrete(iovs_len)) {
+        (Some(iovs), Some(iovs_len)) => (iovs, iovs_len),
+        _ => return Err(ERRNO_INVAL),
+    };
+    match iovs_len.checked_mul(8) {
+        Some(size) if !faults(context, execution, memory, &pointer(iovs), size) => (),
+        _ => return Err(ERRNO_FAULT),
+    }
+
+    let mut iovecs = Vec::new();
+    for i in 0..iovs_len {
+        let memory = &execution.state.memories[&memory];
+        let iovec = pointer(iovs + i * 8);
+        let buf = Val::Sym(SymVal::I32(memory.read(context, &iovec, 4)));
+        let buf_len = Val::Sym(SymVal::I32(memory.read(
+            context,
+            &pointer(iovs + i * 8 + 4),
+            4,
+        )));
+        iovecs.push(match (concrete(&buf), concrete(&buf_len)) {
+            (Some(buf), Some(buf_len)) => (buf, buf_len),
+            _ => return Err(ERRNO_INVAL),
+        });
+    }
+    for (buf, buf_len) in iovecs.iter() {
+        if faults(context, execution, memory, &pointer(*buf), *buf_len) {
+            return Err(ERRNO_FAULT);
+        }
+    }
+    Ok(iovecs)
+}
+
+/// Writes NUL-terminated strings to `buf` and a pointer to each of them to `ptrs`
+fn write_strings<'ctx>(
+    context: &'ctx Context,
+    memory: &mut Memory<'ctx>,
+    strings: &[Vec<BV<'ctx>>],
+    ptrs: u32,
+    buf: u32,
+) {
+    let nul = BV::from_u64(&context.context, 0, 8);
+    let mut offset = buf;
+    for (i, string) in strings.iter().enumerate() {
+        write_u32(context, memory, &pointer(ptrs + i as u32 * 4), offset);
+        for byte in string.iter().chain(std::iter::once(&nul)) {
+            memory.write_byte(context, &pointer(offset), 0, byte);
+            offset += 1;
+        }
+    }
+}
+
+fn sizes_get<'ctx>(
+    context: &'ctx Context,
+    execution: &mut Execution<'ctx>,
+    args: &[Val<'ctx>],
+    strings: &[Vec<BV<'ctx>>],
+) -> HostResult<'ctx> {
+    let memory = match guest_memory(context) {
+        Some(memory) => memory,
+        None => return errno(ERRNO_NOSYS),
+    };
+    if faults(context, execution, memory, &args[0], 4)
+        || faults(context, execution, memory, &args[1], 4)
+    {
+        return errno(ERRNO_FAULT);
+    }
+
+    let memory = execution.state.memories.get_mut(&memory).unwrap();
+    let buf_size = strings.iter().map(|string| string.len() + 1).sum::<usize>();
+    write_u32(context, memory, &args[0], strings.len() as u32);
+    write_u32(context, memory, &args[1], buf_size as u32);
+    errno(ERRNO_SUCCESS)
+}
+
+fn strings_get<'ctx>(
+    context: &'ctx Context,
+    execution: &mut Execution<'ctx>,
+    args: &[Val<'ctx>],
+    strings: &[Vec<BV<'ctx>>],
+) -> HostResult<'ctx> {
+    let memory = match guest_memory(context) {
+        Some(memory) => memory,
+        None => return errno(ERRNO_NOSYS),
+    };
+    let (ptrs, buf) = match (concrete(&args[0]), concrete(&args[1])) {
+        (Some(ptrs), Some(buf)) => (ptrs, buf),
+        _ => return errno(ERRNO_INVAL),
+    };
+    let buf_size = strings.iter().map(|string| string.len() + 1).sum::<usize>();
+    if faults(
+        context,
+        execution,
+        memory,
+        &args[0],
+        strings.len() as u32 * 4,
+    ) || faults(context, execution, memory, &args[1], buf_size as u32)
+    {
+        return errno(ERRNO_FAULT);
+    }
+
+    let memory = execution.state.memories.get_mut(&memory).unwrap();
+    write_strings(context, memory, strings, ptrs, buf);
+    errno(ERRNO_SUCCESS)
+}
+
+/// `fd_write`, appending the bytes written to stdout and stderr to the path's output streams
+#[derive(Clone, Debug)]
+pub struct FdWrite;
+
+impl<'ctx> HostModel<'ctx> for FdWrite {
+    fn call(
+        &mut self,
+        context: &'ctx Context,
+        execution: &mut Execution<'ctx>,
+        args: &[Val<'ctx>],
+        _: &[walrus::ValType],
+    ) -> HostResult<'ctx> {
+        let fd = match concrete(&args[0]) {
+            Some(fd @ (STDOUT | STDERR)) => fd,
+            _ => return errno(ERRNO_BADF),
+        };
+        let memory = match guest_memory(context) {
+            Some(memory) => memory,
+            None => return errno(ERRNO_NOSYS),
+        };
+        let iovecs = match read_iovecs(context, execution, memory, &args[1], &args[2]) {
+            Ok(iovecs) => iovecs,
+            Err(code) => return errno(code),
+        };
+        if faults(context, execution, memory, &args[3], 4) {
+            return errno(ERRNO_FAULT);
+        }
+
+        let memory = execution.state.memories.get_mut(&memory).unwrap();
+        let mut written = 0;
+        for (buf, buf_len) in iovecs {
+            for i in 0..buf_len {
+                let byte = memory.read_byte(context, &pointer(buf), i as usize);
+                execution.state.streams.entry(fd).or_default().push(byte);
+            }
+            written += buf_len;
+        }
+        write_u32(context, memory, &args[3], written);
+        errno(ERRNO_SUCCESS)
+    }
+}
+
+/// `fd_read`, consuming the configured stdin bytes
+#[derive(Clone, Debug)]
+pub struct FdRead<'ctx> {
+    stdin: Rc<Vec<BV<'ctx>>>,
+    position: usize,
+}
+
+impl<'ctx> HostModel<'ctx> for FdRead<'ctx> {
+    fn call(
+        &mut self,
+        context: &'ctx Context,
+        execution: &mut Execution<'ctx>,
+        args: &[Val<'ctx>],
+        _: &[walrus::ValType],
+    ) -> HostResult<'ctx> {
+        if concrete(&args[0]) != Some(STDIN) {
+            return errno(ERRNO_BADF);
+        }
+        let memory = match guest_memory(context) {
+            Some(memory) => memory,
+            None => return errno(ERRNO_NOSYS),
+        };
+        let iovecs = match read_iovecs(context, execution, memory, &args[1], &args[2]) {
+            Ok(iovecs) => iovecs,
+            Err(code) => return errno(code),
+        };
+        if faults(context, execution, memory, &args[3], 4) {
+            return errno(ERRNO_FAULT);
+        }
+
+        let memory = execution.state.memories.get_mut(&memory).unwrap();
+        let mut read = 0;
+        for (buf, buf_len) in iovecs {
+            let len = (buf_len as usize).min(self.stdin.len() - self.position);
+            for i in 0..len {
+                memory.write_byte(context, &pointer(buf), i, &self.stdin[self.position + i]);
+            }
+            self.position += len;
+            read += len as u32;
+        }
+        write_u32(context, memory, &args[3], read);
+        errno(ERRNO_SUCCESS)
+    }
+
+    fn position(&self) -> usize {
+        self.position
+    }
+}
+
+/// `args_sizes_get` and `environ_sizes_get`
+#[derive(Clone, Debug)]
+pub struct SizesGet<'ctx> {
+    strings: Rc<Vec<Vec<BV<'ctx>>>>,
+}
+
+impl<'ctx> HostModel<'ctx> for SizesGet<'ctx> {
+    fn call(
+        &mut self,
+        context: &'ctx Context,
+        execution: &mut Execution<'ctx>,
+        args: &[Val<'ctx>],
+        _: &[walrus::ValType],
+    ) -> HostResult<'ctx> {
+        sizes_get(context, execution, args, &self.strings)
+    }
+}
+
+/// `args_get` and `environ_get`
+#[derive(Clone, Debug)]
+pub struct StringsGet<'ctx> {
+    strings: Rc<Vec<Vec<BV<'ctx>>>>,
+}
+
+impl<'ctx> HostModel<'ctx> for StringsGet<'ctx> {
+    fn call(
+        &mut self,
+        context: &'ctx Context,
+        execution: &mut Execution<'ctx>,
+        args: &[Val<'ctx>],
+        _: &[walrus::ValType],
+    ) -> HostResult<'ctx> {
+        strings_get(context, execution, args, &self.strings)
+    }
+}
+
+/// `proc_exit`, ending the path
+#[derive(Clone, Debug)]
+pub struct ProcExit;
+
+impl<'ctx> HostModel<'ctx> for ProcExit {
+    fn call(
+        &mut self,
+        _: &'ctx Context,
+        _: &mut Execution<'ctx>,
+        _: &[Val<'ctx>],
+        _: &[walrus::ValType],
+    ) -> HostResult<'ctx> {
+        HostResult::Exit
+    }
+}
+
+/// `random_get`, filling the buffer with unconstrained bytes
+#[derive(Clone, Debug)]
+pub struct RandomGet {
+    generated: usize,
+}
+
+impl<'ctx> HostModel<'ctx> for RandomGet {
+    fn call(
+        &mut self,
+        context: &'ctx Context,
+        execution: &mut Execution<'ctx>,
+        args: &[Val<'ctx>],
+        _: &[walrus::ValType],
+    ) -> HostResult<'ctx> {
+        let len = match concrete(&args[1]) {
+            Some(len) => len,
+            None => return errno(ERRNO_INVAL),
+        };
+        let memory = match guest_memory(context) {
+            Some(memory) => memory,
+            None => return errno(ERRNO_NOSYS),
+        };
+        if faults(context, execution, memory, &args[0], len) {
+            return errno(ERRNO_FAULT);
+        }
+
+        let memory = execution.state.memories.get_mut(&memory).unwrap();
+        for i in 0..len {
+            let name = format!("random[{}]", self.generated);
+            let byte = BV::new_const(&context.context, name, 8);
+            memory.write_byte(context, &args[0], i as usize, &byte);
+            self.generated += 1;
+        }
+        errno(ERRNO_SUCCESS)
+    }
+
+    fn position(&self) -> usize {
+        self.generated
+    }
+}
+
+/// `clock_time_get`, returning an unconstrained timestamp
+#[derive(Clone, Debug)]
+pub struct ClockTimeGet;
+
+impl<'ctx> HostModel<'ctx> for ClockTimeGet {
+    fn call(
+        &mut self,
+        context: &'ctx Context,
+        execution: &mut Execution<'ctx>,
+        args: &[Val<'ctx>],
+        _: &[walrus::ValType],
+    ) -> HostResult<'ctx> {
+        let memory = match guest_memory(context) {
+            Some(memory) => memory,
+            None => return errno(ERRNO_NOSYS),
+        };
+        if faults(context, execution, memory, &args[2], 8) {
+            return errno(ERRNO_FAULT);
+        }
+
+        let time = fresh_value(context, walrus::ValType::I64, "time");
+        let memory = execution.state.memories.get_mut(&memory).unwrap();
+        memory.write(context, &args[2], time.as_sym(&context.context).as_i64());
+        errno(ERRNO_SUCCESS)
+    }
+}
+
+impl<'ctx, 'm> Engine<'ctx, 'm> {
+    /// Registers models for the WASI functions, reading stdin, argv and the environment from `config`
+    pub fn add_wasi_models(&mut self, config: WasiConfig<'ctx>) {
+        use walrus::ValType::{I32, I64};
+
+        let args = Rc::new(config.args);
+        let env = Rc::new(config.env);
+
+        self.add_wasi_model("fd_write", &[I32; 4], &[I32], Box::new(FdWrite));
+        self.add_wasi_model(
+            "fd_read",
+            &[I32; 4],
+            &[I32],
+            Box::new(FdRead {
+                stdin: Rc::new(config.stdin),
+                position: 0,
+            }),
+        );
+        self.add_wasi_model(
+            "args_sizes_get",
+            &[I32; 2],
+            &[I32],
+            Box::new(SizesGet {
+                strings: args.clone(),
+            }),
+        );
+        self.add_wasi_model(
+            "args_get",
+            &[I32; 2],
+            &[I32],
+            Box::new(StringsGet { strings: args }),
+        );
+        self.add_wasi_model(
+            "environ_sizes_get",
+            &[I32; 2],
+            &[I32],
+            Box::new(SizesGet {
+                strings: env.clone(),
+            }),
+        );
+        self.add_wasi_model(
+            "environ_get",
+            &[I32; 2],
+            &[I32],
+            Box::new(StringsGet { strings: env }),
+        );
+        self.add_wasi_model("proc_exit", &[I32], &[], Box::new(ProcExit));
+        self.add_wasi_model(
+            "random_get",
+            &[I32; 2],
+            &[I32],
+            Box::new(RandomGet { generated: 0 }),
+        );
+        self.add_wasi_model(
+            "clock_time_get",
+            &[I32, I64, I32],
+            &[I32],
+            Box::new(ClockTimeGet),
+        );
+    }
+
+    /// Registers a model for the WASI function `name`, unless the module imports it with another signature, which
+    /// the model would read the wrong arguments of. Such an import is left unconstrained.
+    fn add_wasi_model(
+        &mut self,
+        name: &str,
+        params: &[walrus::ValType],
+        results: &[walrus::ValType],
+        model: Box<dyn HostModel<'ctx> + 'ctx>,
+    ) {
+        let module = &self.context.module;
+        let ty = module.imports.iter().find_map(|import| match import.kind {
+            walrus::ImportKind::Function(func)
+                if import.module == WASI_MODULE && import.name == name =>
+            {
+                Some(module.types.get(module.funcs.get(func).ty()))
+            }
+            _ => None,
+        });
+        if let Some(ty) = ty {
+            if ty.params() != params || ty.results() != results {
+                warn!(
+                    "Not modelling {}.{}, which is imported as {:?} -> {:?} instead of {:?} -> {:?}",
+                    WASI_MODULE,
+                    name,
+                    ty.params(),
+                    ty.results(),
+                    params,
+                    results
+                );
+                return;
+            }
+        }
+        self.add_host_model(WASI_MODULE, name, model);
+    }
+}
@@ -1,8 +1,15 @@
+pub mod budget;
 pub mod checks;
 pub mod context;
 pub mod engine;
+mod ffi;
 mod flow;
+pub mod host;
+mod loops;
 mod memory;
+mod merge;
 pub mod reporter;
+pub mod search;
 pub mod state;
+mod table;
 pub mod value;
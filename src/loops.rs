@@ -0,0 +1,632 @@
+//! Loop analysis and the summaries and widenings built on it, so that loops don't have to be unrolled to their end.
+//!
+//! A straight-line loop that ends in `br_if` on a comparison of an induction variable with a loop-invariant bound has a
+//! closed-form trip count; the engine jumps such a loop to its last iteration. Any other loop is widened once it gets
+//! too hot, by forgetting the values of everything it modifies and running one more iteration from there.
+use crate::context::Truncation;
+use crate::engine::Engine;
+use crate::host::fresh_value;
+use crate::state::Execution;
+use crate::value::{ConcVal, SymVal, Val};
+use log::trace;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use walrus::ir;
+use z3::ast::{Ast, Bool, BV};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    LtS,
+    LtU,
+    LeS,
+    LeU,
+    GtS,
+    GtU,
+    GeS,
+    GeU,
+}
+
+impl Comparison {
+    /// The comparison and its operand width
+    fn from_op(op: ir::BinaryOp) -> Option<(Comparison, u32)> {
+        use ir::BinaryOp::*;
+        Some(match op {
+            I32Eq => (Comparison::Eq, 32),
+            I32Ne => (Comparison::Ne, 32),
+            I32LtS => (Comparison::LtS, 32),
+            I32LtU => (Comparison::LtU, 32),
+            I32LeS => (Comparison::LeS, 32),
+            I32LeU => (Comparison::LeU, 32),
+            I32GtS => (Comparison::GtS, 32),
+            I32GtU => (Comparison::GtU, 32),
+            I32GeS => (Comparison::GeS, 32),
+            I32GeU => (Comparison::GeU, 32),
+            I64Eq => (Comparison::Eq, 64),
+            I64Ne => (Comparison::Ne, 64),
+            I64LtS => (Comparison::LtS, 64),
+            I64LtU => (Comparison::LtU, 64),
+            I64LeS => (Comparison::LeS, 64),
+            I64LeU => (Comparison::LeU, 64),
+            I64GtS => (Comparison::GtS, 64),
+            I64GtU => (Comparison::GtU, 64),
+            I64GeS => (Comparison::GeS, 64),
+            I64GeU => (Comparison::GeU, 64),
+            _ => return None,
+        })
+    }
+
+    /// The same comparison with its operands swapped
+    fn swap(self) -> Comparison {
+        match self {
+            Comparison::Eq | Comparison::Ne => self,
+            Comparison::LtS => Comparison::GtS,
+            Comparison::LtU => Comparison::GtU,
+            Comparison::LeS => Comparison::GeS,
+            Comparison::LeU => Comparison::GeU,
+            Comparison::GtS => Comparison::LtS,
+            Comparison::GtU => Comparison::LtU,
+            Comparison::GeS => Comparison::LeS,
+            Comparison::GeU => Comparison::LeU,
+        }
+    }
+}
+
+/// Loop-invariant side of the exit test
+#[derive(Debug, Copy, Clone)]
+pub enum Bound {
+    Const(i64),
+    /// A local the loop doesn't modify, plus a constant
+    Local(ir::LocalId, i64),
+}
+
+/// The loop goes round again while `counter + offset <op> bound`, with `counter` read at the start of the iteration
+#[derive(Debug, Clone)]
+pub struct ExitTest {
+    pub op: Comparison,
+    pub width: u32,
+    pub counter: ir::LocalId,
+    pub offset: i64,
+    pub bound: Bound,
+}
+
+#[derive(Debug, Clone)]
+pub struct Summary {
+    /// Loop-carried locals that change and how much each iteration adds to them; every other local the loop writes is
+    /// set before it is read, so it only depends on the last iteration
+    pub inductions: HashMap<ir::LocalId, i64>,
+    pub exit: ExitTest,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct LoopInfo {
+    pub locals: HashSet<ir::LocalId>,
+    pub globals: HashSet<walrus::GlobalId>,
+    pub memories: HashSet<walrus::MemoryId>,
+    /// Blocks nested in the loop, which are entered again on every iteration
+    pub blocks: HashSet<ir::InstrSeqId>,
+    /// False if the loop calls functions or changes state that can't be forgotten, such as tables or memory sizes
+    pub widenable: bool,
+    pub summary: Option<Summary>,
+}
+
+#[derive(Debug)]
+struct LoopVisitor<'a> {
+    info: &'a mut LoopInfo,
+    seq: ir::InstrSeqId,
+}
+
+impl<'instr, 'a> ir::Visitor<'instr> for LoopVisitor<'a> {
+    fn start_instr_seq(&mut self, instr_seq: &'instr ir::InstrSeq) {
+        if instr_seq.id() != self.seq {
+            self.info.blocks.insert(instr_seq.id());
+        }
+    }
+
+    fn visit_instr(&mut self, instr: &'instr ir::Instr, _instr_loc: &'instr ir::InstrLocId) {
+        match instr {
+            ir::Instr::LocalSet(ir::LocalSet { local })
+            | ir::Instr::LocalTee(ir::LocalTee { local }) => {
+                self.info.locals.insert(*local);
+            }
+            ir::Instr::GlobalSet(imm) => {
+                self.info.globals.insert(imm.global);
+            }
+            ir::Instr::Store(ir::Store { memory, .. })
+            | ir::Instr::AtomicRmw(ir::AtomicRmw { memory, .. })
+            | ir::Instr::Cmpxchg(ir::Cmpxchg { memory, .. })
+            | ir::Instr::MemoryFill(ir::MemoryFill { memory })
+            | ir::Instr::MemoryInit(ir::MemoryInit { memory, .. })
+            | ir::Instr::MemoryCopy(ir::MemoryCopy { dst: memory, .. }) => {
+                self.info.memories.insert(*memory);
+            }
+            ir::Instr::Call(_)
+            | ir::Instr::CallIndirect(_)
+            | ir::Instr::MemoryGrow(_)
+            | ir::Instr::DataDrop(_)
+            | ir::Instr::TableSet(_)
+            | ir::Instr::TableGrow(_)
+            | ir::Instr::TableFill(_)
+            | ir::Instr::TableInit(_)
+            | ir::Instr::TableCopy(_)
+            | ir::Instr::ElemDrop(_) => {
+                self.info.widenable = false;
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Finds what a loop modifies and, for straight-line loops, a summary of its iterations
+pub fn analyze_loop(func: &walrus::LocalFunction, seq: ir::InstrSeqId) -> LoopInfo {
+    let mut info = LoopInfo {
+        widenable: true,
+        ..LoopInfo::default()
+    };
+    let mut visitor = LoopVisitor {
+        info: &mut info,
+        seq,
+    };
+    ir::dfs_in_order(&mut visitor, func, seq);
+
+    info.summary = summarize(func, seq);
+    info
+}
+
+fn wrap(value: i64, width: u32) -> i64 {
+    if width == 32 {
+        value as i32 as i64
+    } else {
+        value
+    }
+}
+
+/// A local's value at the start of the iteration plus a constant, or just a constant
+type Affine = (Option<ir::LocalId>, i64);
+
+#[derive(Debug, Clone)]
+enum Abstract {
+    Affine(Affine),
+    Compare(Comparison, u32, Affine, Affine),
+    Unknown,
+}
+
+/// Instructions that can't trap or leave the loop, which are the only ones a summary can skip over
+fn is_pure(instr: &ir::Instr) -> bool {
+    match instr {
+        ir::Instr::Unop(imm) => {
+            !matches!(Truncation::from_op(imm.op), Some(truncation) if !truncation.saturating)
+        }
+        ir::Instr::Binop(imm) => !matches!(
+            imm.op,
+            ir::BinaryOp::I32DivS
+                | ir::BinaryOp::I32DivU
+                | ir::BinaryOp::I32RemS
+                | ir::BinaryOp::I32RemU
+                | ir::BinaryOp::I64DivS
+                | ir::BinaryOp::I64DivU
+                | ir::BinaryOp::I64RemS
+                | ir::BinaryOp::I64RemU
+        ),
+        ir::Instr::Const(_)
+        | ir::Instr::LocalGet(_)
+        | ir::Instr::LocalSet(_)
+        | ir::Instr::LocalTee(_)
+        | ir::Instr::GlobalGet(_)
+        | ir::Instr::MemorySize(_)
+        | ir::Instr::Select(_)
+        | ir::Instr::Drop(_) => true,
+        _ => false,
+    }
+}
+
+/// Evaluates one iteration of a straight-line loop over affine values to find its induction variables and exit test
+fn summarize(func: &walrus::LocalFunction, seq: ir::InstrSeqId) -> Option<Summary> {
+    let block = func.block(seq);
+    let results = match block.ty {
+        ir::InstrSeqType::Simple(result) => result.iter().count(),
+        ir::InstrSeqType::MultiValue(_) => return None,
+    };
+    let (last, body) = block.instrs.split_last()?;
+    match &last.0 {
+        ir::Instr::BrIf(imm) if imm.block == seq => (),
+        _ => return None,
+    }
+
+    let mut stack: Vec<Abstract> = Vec::new();
+    let mut locals: HashMap<ir::LocalId, Abstract> = HashMap::new();
+    let mut carried: HashSet<ir::LocalId> = HashSet::new();
+    for (instr, _) in body {
+        if !is_pure(instr) {
+            return None;
+        }
+        match instr {
+            ir::Instr::Const(imm) => stack.push(match imm.value {
+                ir::Value::I32(value) => Abstract::Affine((None, value as i64)),
+                ir::Value::I64(value) => Abstract::Affine((None, value)),
+                _ => Abstract::Unknown,
+            }),
+            ir::Instr::LocalGet(imm) => stack.push(match locals.get(&imm.local) {
+                Some(value) => value.clone(),
+                None => {
+                    carried.insert(imm.local);
+                    Abstract::Affine((Some(imm.local), 0))
+                }
+            }),
+            ir::Instr::LocalSet(imm) => {
+                locals.insert(imm.local, stack.pop()?);
+            }
+            ir::Instr::LocalTee(imm) => {
+                locals.insert(imm.local, stack.last()?.clone());
+            }
+            ir::Instr::Unop(imm) => {
+                let operand = stack.pop()?;
+                stack.push(match (imm.op, operand) {
+                    (ir::UnaryOp::I32Eqz, Abstract::Affine(value)) => {
+                        Abstract::Compare(Comparison::Eq, 32, value, (None, 0))
+                    }
+                    (ir::UnaryOp::I64Eqz, Abstract::Affine(value)) => {
+                        Abstract::Compare(Comparison::Eq, 64, value, (None, 0))
+                    }
+                    _ => Abstract::Unknown,
+                });
+            }
+            ir::Instr::Binop(imm) => {
+                let rhs = stack.pop()?;
+                let lhs = stack.pop()?;
+                let width = match imm.op {
+                    ir::BinaryOp::I32Add | ir::BinaryOp::I32Sub => 32,
+                    _ => 64,
+                };
+                stack.push(match (imm.op, lhs, rhs) {
+                    (
+                        ir::BinaryOp::I32Add | ir::BinaryOp::I64Add,
+                        Abstract::Affine(a),
+                        Abstract::Affine(b),
+                    ) => match (a, b) {
+                        ((local, a), (None, b)) | ((None, b), (local, a)) => {
+                            Abstract::Affine((local, wrap(a.wrapping_add(b), width)))
+                        }
+                        _ => Abstract::Unknown,
+                    },
+                    (
+                        ir::BinaryOp::I32Sub | ir::BinaryOp::I64Sub,
+                        Abstract::Affine((local, a)),
+                        Abstract::Affine((None, b)),
+                    ) => Abstract::Affine((local, wrap(a.wrapping_sub(b), width))),
+                    (op, Abstract::Affine(a), Abstract::Affine(b)) => match Comparison::from_op(op)
+                    {
+                        Some((comparison, width)) => Abstract::Compare(comparison, width, a, b),
+                        None => Abstract::Unknown,
+                    },
+                    _ => Abstract::Unknown,
+                });
+            }
+            ir::Instr::GlobalGet(_) | ir::Instr::MemorySize(_) => stack.push(Abstract::Unknown),
+            ir::Instr::Select(_) => {
+                stack.truncate(stack.len().checked_sub(3)?);
+                stack.push(Abstract::Unknown);
+            }
+            ir::Instr::Drop(_) => {
+                stack.pop()?;
+            }
+            _ => return None,
+        }
+    }
+
+    let condition = stack.pop()?;
+    if stack.len() != results {
+        return None;
+    }
+
+    // Locals read before they are written carry a value into the next iteration, which has to be a fixed step
+    let mut inductions = HashMap::new();
+    for (local, value) in &locals {
+        if !carried.contains(local) {
+            continue;
+        }
+        match value {
+            // Locals written back unchanged, which includes every float local, keep their value
+            Abstract::Affine((Some(base), 0)) if base == local => (),
+            Abstract::Affine((Some(base), step)) if base == local => {
+                inductions.insert(*local, *step);
+            }
+            _ => return None,
+        }
+    }
+
+    let (op, width, lhs, rhs) = match condition {
+        Abstract::Compare(op, width, lhs, rhs) => (op, width, lhs, rhs),
+        _ => return None,
+    };
+    let is_counter =
+        |(local, _): Affine| matches!(local, Some(local) if inductions.contains_key(&local));
+    let ((counter, offset), op, bound) = if is_counter(lhs) {
+        (lhs, op, rhs)
+    } else if is_counter(rhs) {
+        (rhs, op.swap(), lhs)
+    } else {
+        return None;
+    };
+    let bound = match bound {
+        (None, value) => Bound::Const(value),
+        (Some(local), offset) if !locals.contains_key(&local) => Bound::Local(local, offset),
+        _ => return None,
+    };
+
+    Some(Summary {
+        inductions,
+        exit: ExitTest {
+            op,
+            width,
+            counter: counter.unwrap(),
+            offset,
+            bound,
+        },
+    })
+}
+
+/// Type of a value on the stack, used to forget a loop's params
+fn val_type(value: &Val) -> walrus::ValType {
+    match value {
+        Val::Conc(ConcVal(ir::Value::I32(_))) | Val::Sym(SymVal::I32(_)) => walrus::ValType::I32,
+        Val::Conc(ConcVal(ir::Value::I64(_))) | Val::Sym(SymVal::I64(_)) => walrus::ValType::I64,
+        Val::Conc(ConcVal(ir::Value::F32(_))) | Val::Sym(SymVal::F32(_)) => walrus::ValType::F32,
+        Val::Conc(ConcVal(ir::Value::F64(_))) | Val::Sym(SymVal::F64(_)) => walrus::ValType::F64,
+        Val::Conc(ConcVal(ir::Value::V128(_))) => walrus::ValType::V128,
+    }
+}
+
+fn as_int<'a, 'ctx>(value: &'a SymVal<'ctx>) -> &'a BV<'ctx> {
+    match value {
+        SymVal::I32(value) | SymVal::I64(value) => value,
+        _ => unreachable!(),
+    }
+}
+
+/// Multiplicative inverse of an odd number modulo 2^64, by Newton's iteration
+fn inverse(value: u64) -> u64 {
+    let mut inverse = value;
+    for _ in 0..5 {
+        inverse = inverse.wrapping_mul(2u64.wrapping_sub(value.wrapping_mul(inverse)));
+    }
+    inverse
+}
+
+impl<'ctx, 'm> Engine<'ctx, 'm> {
+    fn constant(&self, value: i64, width: u32) -> BV<'ctx> {
+        let mask = if width == 64 {
+            u64::MAX
+        } else {
+            (1 << width) - 1
+        };
+        BV::from_u64(&self.context.context, value as u64 & mask, width)
+    }
+
+    /// Number of iterations after the first one that the loop runs for, and the condition under which that count holds
+    fn get_trip_count(
+        &self,
+        execution: &Execution<'ctx>,
+        summary: &Summary,
+    ) -> Option<(BV<'ctx>, Bool<'ctx>)> {
+        let context = &self.context.context;
+        let exit = &summary.exit;
+        let width = exit.width;
+        let locals = &execution.state.call_stack.last().unwrap().locals;
+        let local = |local: &ir::LocalId| as_int(&locals[local].as_sym(context)).clone();
+
+        let step = wrap(summary.inductions[&exit.counter], width);
+        let mut value = local(&exit.counter).bvadd(&self.constant(exit.offset, width));
+        let mut bound = match exit.bound {
+            Bound::Const(bound) => self.constant(bound, width),
+            Bound::Local(bound, offset) => local(&bound).bvadd(&self.constant(offset, width)),
+        };
+        let zero = self.constant(0, width);
+        let one = self.constant(1, width);
+
+        let op = match exit.op {
+            Comparison::Eq => {
+                return Some((
+                    value._eq(&bound).ite(&one, &zero),
+                    Bool::from_bool(context, true),
+                ))
+            }
+            Comparison::Ne if step % 2 != 0 => {
+                let step = self.constant(inverse(step as u64) as i64, width);
+                return Some((
+                    bound.bvsub(&value).bvmul(&step),
+                    Bool::from_bool(context, true),
+                ));
+            }
+            Comparison::Ne => return None,
+            // Moving the signed range onto the unsigned one keeps steps the same
+            Comparison::LtS | Comparison::LeS | Comparison::GtS | Comparison::GeS => {
+                let min = self.constant(i64::MIN >> (64 - width), width);
+                value = value.bvadd(&min);
+                bound = bound.bvadd(&min);
+                match exit.op {
+                    Comparison::LtS => Comparison::LtU,
+                    Comparison::LeS => Comparison::LeU,
+                    Comparison::GtS => Comparison::GtU,
+                    _ => Comparison::GeU,
+                }
+            }
+            op => op,
+        };
+
+        // Inclusive bounds become exclusive ones, except where that would make the loop endless
+        let mut guards = Vec::new();
+        let op = match op {
+            Comparison::LeU => {
+                guards.push(bound._eq(&self.constant(-1, width)).not());
+                bound = bound.bvadd(&one);
+                Comparison::LtU
+            }
+            Comparison::GeU => {
+                guards.push(bound._eq(&zero).not());
+                bound = bound.bvsub(&one);
+                Comparison::GtU
+            }
+            op => op,
+        };
+
+        // Counting towards the bound must not wrap around before passing it
+        let (distance, done, no_wrap, step) = match op {
+            Comparison::LtU if step > 0 => (
+                bound.bvsub(&value),
+                value.bvuge(&bound),
+                bound.bvule(&self.constant(-step, width)),
+                step,
+            ),
+            Comparison::GtU if step < 0 => (
+                value.bvsub(&bound),
+                value.bvule(&bound),
+                bound.bvuge(&self.constant(-step - 1, width)),
+                -step,
+            ),
+            _ => return None,
+        };
+        let step = self.constant(step, width);
+        let trips = distance.bvadd(&step).bvsub(&one).bvudiv(&step);
+        guards.push(Bool::or(context, &[&done, &no_wrap]));
+
+        let guards = guards.iter().collect::<Vec<_>>();
+        Some((done.ite(&zero, &trips), Bool::and(context, &guards)))
+    }
+
+    /// Pushes an execution that just entered a loop, moved to the start of the loop's last iteration if it has a
+    /// summary. The part of the execution for which the trip count doesn't hold is forked off to unroll the loop.
+    pub fn enter_loop(&mut self, mut execution: Execution<'ctx>) {
+        let summary = self
+            .get_loop_info(&execution)
+            .and_then(|info| info.summary.clone());
+        let (trips, guard) = match summary
+            .as_ref()
+            .and_then(|summary| self.get_trip_count(&execution, summary))
+        {
+            Some((trips, guard)) => (trips, guard.simplify()),
+            None => {
+                self.push_execution(execution);
+                return;
+            }
+        };
+        let summary = summary.unwrap();
+
+        match self.get_feasible_sides(&mut execution, &guard) {
+            (true, false) => (),
+            (false, _) => {
+                self.push_execution(execution);
+                return;
+            }
+            (true, true) => {
+                let mut unrolled = Execution::from(&mut execution, self.context);
+                unrolled.add_constraint(guard.not());
+
+                trace!(
+                    "Forking execution #{} on loop summary -> [summarized: #{}/unrolled: #{}]",
+                    execution.id,
+                    execution.id,
+                    unrolled.id
+                );
+
+                self.push_execution(unrolled);
+                execution.add_constraint(guard);
+            }
+        }
+
+        trace!(
+            "Summarizing loop {:?} of execution #{} as {} more iterations",
+            execution.cur_block,
+            execution.id,
+            trips.simplify()
+        );
+
+        let context = &self.context.context;
+        let width = summary.exit.width;
+        let frame = execution.state.call_stack.last_mut().unwrap();
+        let updates = summary
+            .inductions
+            .iter()
+            .map(|(local, step)| {
+                let value = frame.locals[local].as_sym(context);
+                let value = as_int(&value);
+                let local_width = value.get_size();
+                let trips = match local_width.cmp(&width) {
+                    Ordering::Equal => trips.clone(),
+                    Ordering::Greater => trips.zero_ext(local_width - width),
+                    Ordering::Less => trips.extract(local_width - 1, 0),
+                };
+                let value = value
+                    .bvadd(&trips.bvmul(&self.constant(*step, local_width)))
+                    .simplify();
+                let value = if local_width == 32 {
+                    SymVal::I32(value)
+                } else {
+                    SymVal::I64(value)
+                };
+                (*local, Val::Sym(value))
+            })
+            .collect::<Vec<_>>();
+        frame.locals.extend(updates);
+
+        self.push_execution(execution);
+    }
+
+    /// Forgets everything a hot loop modifies so that one more iteration covers all the remaining ones, and cuts off
+    /// that iteration's way back to the loop. Returns false if the loop can't be widened.
+    pub fn widen_loop(&self, execution: &mut Execution<'ctx>, info: &LoopInfo) -> bool {
+        if !info.widenable
+            || execution.cur_location.is_some()
+            || !execution.widened.insert(execution.cur_block)
+        {
+            return false;
+        }
+
+        trace!(
+            "Widening loop {:?} of execution #{}",
+            execution.cur_block,
+            execution.id
+        );
+
+        let context = self.context;
+        let module = &context.module;
+        let frame = execution.state.call_stack.last_mut().unwrap();
+        let (params, _) = self.get_block_arity(frame.func, &execution.cur_block);
+        let height = frame.value_stack.len() - params;
+        for value in &mut frame.value_stack[height..] {
+            *value = fresh_value(context, val_type(value), "loop");
+        }
+        for local in &info.locals {
+            let ty = module.locals.get(*local).ty();
+            frame
+                .locals
+                .insert(*local, fresh_value(context, ty, "loop"));
+        }
+        for global in &info.globals {
+            let ty = module.globals.get(*global).ty;
+            execution
+                .state
+                .globals
+                .insert(*global, fresh_value(context, ty, "loop"));
+        }
+        for memory in &info.memories {
+            let memory = execution.state.memories.get_mut(memory).unwrap();
+            memory.array = z3::ast::Array::fresh_const(
+                &context.context,
+                "loop",
+                &z3::Sort::bitvector(&context.context, 32),
+                &z3::Sort::bitvector(&context.context, 8),
+            );
+        }
+
+        // The widened iteration runs at the limit, and nested loops start counting again in it
+        execution
+            .hotness
+            .retain(|block, _| !info.blocks.contains(block));
+        execution
+            .hotness
+            .insert(execution.cur_block, self.max_hotness);
+        true
+    }
+}
@@ -1,22 +1,86 @@
 use crate::{
+    context::Context,
     engine::Engine,
+    ffi::{float, lambda},
+    state::{Execution, State, Status, TrapReason},
     value::{ConcVal, SymVal, Val},
 };
+use std::rc::Rc;
 use walrus::ir;
-use z3;
+use z3::ast::{Array, Ast, BV};
 
 pub const PAGE_SIZE: u32 = 65536;
+pub const MAX_PAGES: u32 = 65536;
+
+/// Bulk operations longer than this are encoded with a lambda instead of individual stores
+const MAX_UNROLLED: u32 = 1024;
+
+fn concrete_len(len: &Val) -> Option<u32> {
+    match len {
+        Val::Conc(val) if val.as_i32() as u32 <= MAX_UNROLLED => Some(val.as_i32() as u32),
+        _ => None,
+    }
+}
+
+/// Byte at `index` of `data[lo..hi]`, a balanced `ite` over the index so that the term stays linear in the length of
+/// the segment and only `log2(len)` comparisons deep
+fn data_tree<'ctx>(
+    context: &'ctx Context,
+    data: &[u8],
+    index: &BV<'ctx>,
+    lo: usize,
+    hi: usize,
+) -> BV<'ctx> {
+    if hi - lo == 1 {
+        return BV::from_u64(&context.context, data[lo] as u64, 8);
+    }
+    let mid = lo + (hi - lo) / 2;
+    index
+        .bvult(&BV::from_u64(&context.context, mid as u64, 32))
+        .ite(
+            &data_tree(context, data, index, lo, mid),
+            &data_tree(context, data, index, mid, hi),
+        )
+}
+
+/// `data` as an array from offsets in the segment to bytes, zero past its end
+fn data_array<'ctx>(context: &'ctx Context, data: &[u8]) -> Array<'ctx> {
+    let index = BV::fresh_const(&context.context, "offset", 32);
+    let len = BV::from_u64(&context.context, data.len() as u64, 32);
+    let byte = match data.len() {
+        0 => context.zero(8),
+        _ => index.bvult(&len).ite(
+            &data_tree(context, data, &index, 0, data.len()),
+            &context.zero(8),
+        ),
+    };
+    lambda(&index, &byte)
+}
+
+/// Address accessed by a load or store, `base + offset` as a 64-bit value so that it cannot wrap around
+pub fn effective_address<'ctx>(
+    context: &'ctx Context,
+    base_index: &Val<'ctx>,
+    offset: u32,
+) -> BV<'ctx> {
+    let base_index = base_index.as_sym(&context.context).as_i32().zero_ext(32);
+    base_index
+        .bvadd(&BV::from_u64(&context.context, offset as u64, 64))
+        .simplify()
+}
 
 #[derive(Debug, Clone)]
 pub struct Memory<'ctx> {
     pub size: Val<'ctx>, // size in pages
+    pub maximum: u32,    // in pages
     pub array: z3::ast::Array<'ctx>,
 }
 
 impl<'ctx> Memory<'ctx> {
-    pub fn new(context: &'ctx z3::Context, initial: u32) -> Self {
+    pub fn new(context: &'ctx z3::Context, initial: u32, maximum: Option<u32>) -> Self {
         Memory {
             size: Val::Conc(ConcVal(ir::Value::I32(initial as i32))),
+            maximum: maximum.unwrap_or(MAX_PAGES),
             array: z3::ast::Array::const_array(
                 context,
                 &z3::Sort::bitvector(context, 32),
@@ -24,45 +88,262 @@ impl<'ctx> Memory<'ctx> {
             ),
         }
     }
+
+    /// Whether growing by `num_pages` stays within the maximum size
+    pub fn can_grow(&self, context: &'ctx Context, num_pages: &Val<'ctx>) -> z3::ast::Bool<'ctx> {
+        let size = self.size.as_sym(&context.context).as_i32().zero_ext(32);
+        let num_pages = num_pages.as_sym(&context.context).as_i32().zero_ext(32);
+        let maximum = BV::from_u64(&context.context, self.maximum as u64, 64);
+        size.bvadd(&num_pages).bvule(&maximum)
+    }
+
+    /// Whether any of the `len` bytes starting at an effective address lies past the end of the memory
+    pub fn out_of_bounds(
+        &self,
+        context: &'ctx Context,
+        address: &BV<'ctx>,
+        len: &Val<'ctx>,
+    ) -> z3::ast::Bool<'ctx> {
+        let end = address.bvadd(&len.as_sym(&context.context).as_i32().zero_ext(32));
+        let byte_size = self
+            .size
+            .as_sym(&context.context)
+            .as_i32()
+            .zero_ext(32)
+            .bvmul(&BV::from_u64(&context.context, PAGE_SIZE as u64, 64));
+        end.bvugt(&byte_size)
+    }
+
+    fn address(context: &'ctx Context, base_index: &Val<'ctx>, offset: usize) -> BV<'ctx> {
+        let index = context
+            .bin_op(
+                ir::BinaryOp::I32Add,
+                base_index,
+                &Val::Conc(ConcVal(ir::Value::I32(offset as i32))),
+            )
+            .unwrap();
+        index.as_sym(&context.context).as_i32().clone()
+    }
+
+    /// Sets each address `i` in `[dest, dest + len)` to `source(i)`, for any `len`
+    fn write_range(
+        &mut self,
+        context: &'ctx Context,
+        dest: &Val<'ctx>,
+        len: &Val<'ctx>,
+        source: impl Fn(&BV<'ctx>) -> BV<'ctx>,
+    ) {
+        let dest = dest.as_sym(&context.context).as_i32().clone();
+        let len = len.as_sym(&context.context).as_i32().clone();
+        let index = BV::fresh_const(&context.context, "index", 32);
+        let in_range = index.bvsub(&dest).bvult(&len);
+        let old = self.array.select(&index).as_bv().unwrap();
+        self.array = lambda(&index, &in_range.ite(&source(&index), &old));
+    }
+
+    /// `memory.fill` with the low byte of `value`
+    pub fn fill(
+        &mut self,
+        context: &'ctx Context,
+        dest: &Val<'ctx>,
+        value: &Val<'ctx>,
+        len: &Val<'ctx>,
+    ) {
+        let byte = value.as_sym(&context.context).as_i32().extract(7, 0);
+        match concrete_len(len) {
+            Some(len) => {
+                for i in 0..len {
+                    self.write_byte(context, dest, i as usize, &byte);
+                }
+            }
+            None => self.write_range(context, dest, len, |_| byte.clone()),
+        }
+    }
+
+    /// `memory.copy` from a snapshot of `source`, which may be this memory, so overlapping ranges are correct
+    pub fn copy(
+        &mut self,
+        context: &'ctx Context,
+        dest: &Val<'ctx>,
+        source: &Memory<'ctx>,
+        src: &Val<'ctx>,
+        len: &Val<'ctx>,
+    ) {
+        match concrete_len(len) {
+            Some(len) => {
+                let bytes = (0..len)
+                    .map(|i| source.read_byte(context, src, i as usize))
+                    .collect::<Vec<_>>();
+                for (i, byte) in bytes.iter().enumerate() {
+                    self.write_byte(context, dest, i, byte);
+                }
+            }
+            None => {
+                let old = source.array.clone();
+                let dest_index = dest.as_sym(&context.context).as_i32().clone();
+                let src_index = src.as_sym(&context.context).as_i32().clone();
+                self.write_range(context, dest, len, |index| {
+                    let index = src_index.bvadd(&index.bvsub(&dest_index));
+                    old.select(&index).as_bv().unwrap()
+                });
+            }
+        }
+    }
+
+    /// `memory.init` from a data segment, which must contain `[src, src + len)`
+    pub fn init(
+        &mut self,
+        context: &'ctx Context,
+        dest: &Val<'ctx>,
+        src: &Val<'ctx>,
+        len: &Val<'ctx>,
+        data: &[u8],
+    ) {
+        match (src, concrete_len(len)) {
+            (Val::Conc(src), Some(len)) => {
+                let src = src.as_i32() as u32 as usize;
+                for (i, byte) in data[src..src + len as usize].iter().enumerate() {
+                    let byte = BV::from_u64(&context.context, *byte as u64, 8);
+                    self.write_byte(context, dest, i, &byte);
+                }
+            }
+            _ => {
+                let segment = data_array(context, data);
+                let dest_index = dest.as_sym(&context.context).as_i32().clone();
+                let src_index = src.as_sym(&context.context).as_i32().clone();
+                self.write_range(context, dest, len, |index| {
+                    let index = src_index.bvadd(&index.bvsub(&dest_index));
+                    segment.select(&index).as_bv().unwrap()
+                });
+            }
+        }
+    }
+
+    /// Copies all of `data` to `dest` as one term, for the active data segments
+    pub fn write_data(&mut self, context: &'ctx Context, dest: &Val<'ctx>, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        let len = Val::Conc(ConcVal(ir::Value::I32(data.len() as i32)));
+        let segment = data_array(context, data);
+        let dest_index = dest.as_sym(&context.context).as_i32().clone();
+        self.write_range(context, dest, &len, |index| {
+            segment.select(&index.bvsub(&dest_index)).as_bv().unwrap()
+        });
+    }
+
+    /// Reads `num_bytes` little-endian bytes starting at `base_index`
+    pub fn read(
+        &self,
+        context: &'ctx Context,
+        base_index: &Val<'ctx>,
+        num_bytes: usize,
+    ) -> BV<'ctx> {
+        let mut value = self.read_byte(context, base_index, num_bytes - 1);
+        for i in (0..num_bytes - 1).rev() {
+            value = value.concat(&self.read_byte(context, base_index, i));
+        }
+        value
+    }
+
+    pub fn read_byte(
+        &self,
+        context: &'ctx Context,
+        base_index: &Val<'ctx>,
+        offset: usize,
+    ) -> BV<'ctx> {
+        let index = Self::address(context, base_index, offset);
+        self.array.select(&index).as_bv().unwrap()
+    }
+
+    /// Writes a value whose size is a multiple of 8 as little-endian bytes starting at `base_index`
+    pub fn write(&mut self, context: &'ctx Context, base_index: &Val<'ctx>, value: &BV<'ctx>) {
+        for i in 0..(value.get_size() / 8) {
+            let byte = value.extract(i * 8 + 7, i * 8);
+            self.write_byte(context, base_index, i as usize, &byte);
+        }
+    }
+
+    pub fn write_byte(
+        &mut self,
+        context: &'ctx Context,
+        base_index: &Val<'ctx>,
+        offset: usize,
+        byte: &BV<'ctx>,
+    ) {
+        let index = Self::address(context, base_index, offset);
+        self.array = self.array.store(&index, byte);
+    }
 }
 
 impl<'ctx, 'm> Engine<'ctx, 'm> {
+    /// Copies the active data segments into memory and keeps the passive ones for `memory.init`. A segment that
+    /// doesn't fit in its memory fails instantiation.
+    pub fn init_data(&self, state: &mut State<'ctx>) -> Result<(), TrapReason> {
+        for data in self.context.module.data.iter() {
+            match &data.kind {
+                walrus::DataKind::Active(active) => {
+                    let offset = match active.location {
+                        walrus::ActiveDataLocation::Absolute(offset) => {
+                            Val::Conc(ConcVal(ir::Value::I32(offset as i32)))
+                        }
+                        walrus::ActiveDataLocation::Relative(global) => {
+                            state.globals.get(&global).unwrap().clone()
+                        }
+                    };
+                    // A symbolic offset can't be checked without forking, so it is assumed to fit
+                    if let Val::Conc(offset) = &offset {
+                        let initial = self.context.module.memories.get(active.memory).initial;
+                        let end = offset.as_i32() as u32 as u64 + data.value.len() as u64;
+                        if end > initial as u64 * PAGE_SIZE as u64 {
+                            return Err(TrapReason::MemoryOutOfBounds);
+                        }
+                    }
+                    let memory = state.memories.get_mut(&active.memory).unwrap();
+                    memory.write_data(self.context, &offset, &data.value);
+
+                    // Active segments are dropped once instantiation has copied them
+                    state.data.insert(data.id(), Rc::from(Vec::new()));
+                }
+                walrus::DataKind::Passive => {
+                    state
+                        .data
+                        .insert(data.id(), Rc::from(data.value.as_slice()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Index into the memory array of an in-bounds effective address
+    fn array_index(&self, base_index: &Val<'ctx>, offset: u32) -> Val<'ctx> {
+        let address = effective_address(self.context, base_index, offset);
+        Val::Sym(SymVal::I32(address.extract(31, 0).simplify()))
+    }
+
     pub fn do_load(
         &self,
         memory: &Memory<'ctx>,
         base_index: &Val<'ctx>,
+        offset: u32,
         load_size: u32,
         size: u32,
         zero_extend: bool,
     ) -> Val<'ctx> {
-        let num_bytes = (load_size / 8) as usize;
-        let mut bytes = vec![];
-        for i in 0..num_bytes {
-            let index_val = self
-                .bin_op(
-                    ir::BinaryOp::I32Add,
-                    &base_index,
-                    &Val::Conc(ConcVal(ir::Value::I32(i as i32))),
-                )
-                .unwrap()
-                .as_sym(&self.context.context);
-            let index = index_val.as_i32();
-            bytes.push(memory.array.select(index).as_bv().unwrap());
-        }
-
-        let mut value = bytes[num_bytes - 1].clone();
-        for i in 0..(num_bytes - 1) {
-            value = bytes[num_bytes - i - 2].concat(&value);
-        }
-
-        if size == load_size {
-            Val::Sym(SymVal::I32(value))
+        let index = self.array_index(base_index, offset);
+        let value = memory.read(self.context, &index, (load_size / 8) as usize);
+        let value = if size == load_size {
+            value
+        } else if zero_extend {
+            value.zero_ext(size - load_size)
         } else {
-            if zero_extend {
-                Val::Sym(SymVal::I32(value.zero_ext(size - load_size)))
-            } else {
-                Val::Sym(SymVal::I32(value.sign_ext(size - load_size)))
-            }
+            value.sign_ext(size - load_size)
+        };
+
+        match size {
+            32 => Val::Sym(SymVal::I32(value)),
+            64 => Val::Sym(SymVal::I64(value)),
+            _ => unreachable!(),
         }
     }
 
@@ -70,25 +351,111 @@ impl<'ctx, 'm> Engine<'ctx, 'm> {
         &self,
         memory: &mut Memory<'ctx>,
         base_index: &Val<'ctx>,
+        offset: u32,
         value: Val<'ctx>,
         store_size: u32,
     ) {
-        let num_bytes = (store_size / 8) as usize;
+        let index = self.array_index(base_index, offset);
         let sym_val = value.as_sym(&self.context.context);
-        let value = sym_val.as_i32();
-
-        for i in 0..num_bytes {
-            let index_val = self
-                .bin_op(
-                    ir::BinaryOp::I32Add,
-                    &base_index,
-                    &Val::Conc(ConcVal(ir::Value::I32(i as i32))),
-                )
-                .unwrap()
-                .as_sym(&self.context.context);
-            let index = index_val.as_i32();
-            let stored = value.extract((i * 8 + 7) as u32, (i * 8) as u32);
-            memory.array = memory.array.store(index, &stored);
+        let value = match &sym_val {
+            SymVal::I32(value) | SymVal::I64(value) => value.clone(),
+            SymVal::F32(value) | SymVal::F64(value) => float::to_bits(value),
+        };
+        memory.write(self.context, &index, &value.extract(store_size - 1, 0));
+    }
+
+    /// Runs an atomic read-modify-write, compare-exchange, notify or wait on the current frame's operands.
+    /// Executions are single-threaded, so `notify` wakes no one and a `wait` on a matching value can only time out.
+    /// Returns false if the path ended in a trap.
+    pub fn do_atomic(&mut self, execution: &mut Execution<'ctx>, instr: &ir::Instr) -> bool {
+        let (memory_id, offset, bytes, operands) = match instr {
+            ir::Instr::AtomicRmw(imm) => (imm.memory, imm.arg.offset, imm.width.bytes(), 1),
+            ir::Instr::Cmpxchg(imm) => (imm.memory, imm.arg.offset, imm.width.bytes(), 2),
+            ir::Instr::AtomicNotify(imm) => (imm.memory, imm.arg.offset, 4, 1),
+            ir::Instr::AtomicWait(imm) => (
+                imm.memory,
+                imm.arg.offset,
+                if imm.sixty_four { 8 } else { 4 },
+                2,
+            ),
+            _ => unreachable!(),
+        };
+        let frame = execution.state.call_stack.last_mut().unwrap();
+        let operands = frame
+            .value_stack
+            .split_off(frame.value_stack.len() - operands);
+        let index = frame.value_stack.pop().unwrap();
+
+        let len = Val::Conc(ConcVal(ir::Value::I32(bytes as i32)));
+        let in_bounds = self.get_in_bounds(execution, memory_id, &index, offset, &len);
+        if !self.do_bounds_check(execution, in_bounds)
+            || !self.do_alignment_check(execution, &index, offset, bytes)
+        {
+            return false;
         }
+        if let ir::Instr::AtomicWait(_) = instr {
+            if !self.context.module.memories.get(memory_id).shared {
+                execution.status = Status::Trap(TrapReason::ExpectedSharedMemory);
+                return false;
+            }
+        }
+
+        let context = &self.context.context;
+        let size = match instr {
+            ir::Instr::AtomicRmw(ir::AtomicRmw { width, .. })
+            | ir::Instr::Cmpxchg(ir::Cmpxchg { width, .. }) => match width {
+                ir::AtomicWidth::I32 | ir::AtomicWidth::I32_8 | ir::AtomicWidth::I32_16 => 32,
+                _ => 64,
+            },
+            _ => bytes * 8,
+        };
+        let as_int = |value: &Val<'ctx>| match value.as_sym(context) {
+            SymVal::I32(value) | SymVal::I64(value) => value,
+            _ => unreachable!(),
+        };
+        let from_int = |value: BV<'ctx>| match value.get_size() {
+            32 => Val::Sym(SymVal::I32(value)),
+            _ => Val::Sym(SymVal::I64(value)),
+        };
+
+        let memory = execution.state.memories.get_mut(&memory_id).unwrap();
+        let old = self.do_load(memory, &index, offset, bytes * 8, size, true);
+        let result = match instr {
+            ir::Instr::AtomicRmw(imm) => {
+                let (old, value) = (as_int(&old), as_int(&operands[0]));
+                let new = match imm.op {
+                    ir::AtomicOp::Add => old.bvadd(&value),
+                    ir::AtomicOp::Sub => old.bvsub(&value),
+                    ir::AtomicOp::And => old.bvand(&value),
+                    ir::AtomicOp::Or => old.bvor(&value),
+                    ir::AtomicOp::Xor => old.bvxor(&value),
+                    ir::AtomicOp::Xchg => value,
+                };
+                self.do_store(memory, &index, offset, from_int(new), bytes * 8);
+                old
+            }
+            ir::Instr::Cmpxchg(_) => {
+                let (old, replacement) = (as_int(&old), as_int(&operands[1]));
+                // The expected value is wrapped to the access width before comparing
+                let mut expected = as_int(&operands[0]).extract(bytes * 8 - 1, 0);
+                if bytes * 8 < size {
+                    expected = expected.zero_ext(size - bytes * 8);
+                }
+                let new = old._eq(&expected).ite(&replacement, &old);
+                self.do_store(memory, &index, offset, from_int(new), bytes * 8);
+                old
+            }
+            ir::Instr::AtomicNotify(_) => self.zero(32),
+            ir::Instr::AtomicWait(_) => {
+                // 1 if the value differs, otherwise 2 for timing out
+                let differs = as_int(&old)._eq(&as_int(&operands[0])).not();
+                differs.ite(&self.context.one(32), &BV::from_u64(context, 2, 32))
+            }
+            _ => unreachable!(),
+        };
+
+        let frame = execution.state.call_stack.last_mut().unwrap();
+        frame.value_stack.push(from_int(result));
+        true
     }
 }
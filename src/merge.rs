@@ -0,0 +1,289 @@
+//! Merging of executions that meet at the same location, so that sequential branches don't multiply paths.
+//!
+//! Values that differ between the two paths become `ite`s over the part of their path conditions that differs.
+use crate::context::Context;
+use crate::engine::Engine;
+use crate::memory::Memory;
+use crate::state::{Execution, Frame, State, Status};
+use crate::value::{SymVal, Val};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use z3::ast::{Ast, Bool, Dynamic};
+
+fn same_value(a: &Val, b: &Val) -> bool {
+    match (a, b) {
+        (Val::Conc(a), Val::Conc(b)) => match (a.0, b.0) {
+            // Compared by bits so that NaNs and signed zeros are told apart
+            (walrus::ir::Value::F32(a), walrus::ir::Value::F32(b)) => a.to_bits() == b.to_bits(),
+            (walrus::ir::Value::F64(a), walrus::ir::Value::F64(b)) => a.to_bits() == b.to_bits(),
+            (a, b) => format!("{:?}", a) == format!("{:?}", b),
+        },
+        (Val::Sym(SymVal::I32(a)), Val::Sym(SymVal::I32(b)))
+        | (Val::Sym(SymVal::I64(a)), Val::Sym(SymVal::I64(b))) => a == b,
+        (Val::Sym(SymVal::F32(a)), Val::Sym(SymVal::F32(b)))
+        | (Val::Sym(SymVal::F64(a)), Val::Sym(SymVal::F64(b))) => a == b,
+        _ => false,
+    }
+}
+
+fn merge_value<'ctx>(
+    context: &'ctx Context,
+    condition: &Bool<'ctx>,
+    a: &Val<'ctx>,
+    b: &Val<'ctx>,
+) -> Val<'ctx> {
+    if same_value(a, b) {
+        a.clone()
+    } else {
+        Val::Sym(SymVal::ite(
+            condition,
+            &a.as_sym(&context.context),
+            &b.as_sym(&context.context),
+        ))
+    }
+}
+
+/// Block heights are not compared: those of the enclosing blocks agree at the same location, and the rest are stale
+fn same_frame(a: &Frame, b: &Frame) -> bool {
+    a.func == b.func
+        && a.ret.map(|ret| (ret.block, ret.loc)) == b.ret.map(|ret| (ret.block, ret.loc))
+        && a.value_stack.len() == b.value_stack.len()
+        && a.locals.len() == b.locals.len()
+        && a.locals.keys().all(|local| b.locals.contains_key(local))
+}
+
+/// Whether the parts of the state that can't be combined with `ite` agree
+fn same_shape(a: &State, b: &State) -> bool {
+    a.call_stack.len() == b.call_stack.len()
+        && a.call_stack
+            .iter()
+            .zip(&b.call_stack)
+            .all(|(a, b)| same_frame(a, b))
+        && a.data == b.data
+        && a.tables == b.tables
+        && a.memories.len() == b.memories.len()
+        && a.memories
+            .keys()
+            .all(|memory| b.memories.contains_key(memory))
+        && a.streams.len() == b.streams.len()
+        && a.streams.iter().all(
+            |(fd, bytes)| matches!(b.streams.get(fd), Some(other) if other.len() == bytes.len()),
+        )
+}
+
+/// Pairs of values that would become an `ite` if the two states were merged
+fn differences<'ctx>(
+    context: &'ctx Context,
+    a: &State<'ctx>,
+    b: &State<'ctx>,
+) -> Vec<(Dynamic<'ctx>, Dynamic<'ctx>)> {
+    let mut values: Vec<(&Val<'ctx>, &Val<'ctx>)> = Vec::new();
+    for (a, b) in a.call_stack.iter().zip(&b.call_stack) {
+        values.extend(a.value_stack.iter().zip(&b.value_stack));
+        values.extend(
+            a.locals
+                .iter()
+                .map(|(local, value)| (value, &b.locals[local])),
+        );
+    }
+    values.extend(
+        a.globals
+            .iter()
+            .map(|(global, value)| (value, &b.globals[global])),
+    );
+    values.extend(
+        a.memories
+            .iter()
+            .map(|(id, memory)| (&memory.size, &b.memories[id].size)),
+    );
+
+    let sym = |value: &Val<'ctx>| match value.as_sym(&context.context) {
+        SymVal::I32(value) | SymVal::I64(value) => Dynamic::from_ast(&value),
+        SymVal::F32(value) | SymVal::F64(value) => Dynamic::from_ast(&value),
+    };
+    let mut pairs = values
+        .into_iter()
+        .filter(|(a, b)| !same_value(a, b))
+        .map(|(a, b)| (sym(a), sym(b)))
+        .collect::<Vec<_>>();
+    for (id, memory) in &a.memories {
+        let other = &b.memories[id];
+        if memory.array != other.array {
+            pairs.push((
+                Dynamic::from_ast(&memory.array),
+                Dynamic::from_ast(&other.array),
+            ));
+        }
+    }
+    for (fd, bytes) in &a.streams {
+        for (byte, other) in bytes.iter().zip(&b.streams[fd]) {
+            if byte != other {
+                pairs.push((Dynamic::from_ast(byte), Dynamic::from_ast(other)));
+            }
+        }
+    }
+    pairs
+}
+
+/// Number of distinct nodes in `terms`, counting no further than `limit + 1`
+fn term_size<'ctx>(terms: &[&Dynamic<'ctx>], limit: usize) -> usize {
+    let mut seen = HashSet::new();
+    let mut pending: Vec<Dynamic<'ctx>> = terms.iter().map(|term| (*term).clone()).collect();
+    while let Some(term) = pending.pop() {
+        if seen.len() > limit {
+            break;
+        }
+        if !seen.contains(&term) {
+            pending.extend(term.children());
+            seen.insert(term);
+        }
+    }
+    seen.len()
+}
+
+/// Number of leading constraints the two paths share, and what each adds after them
+fn path_suffixes<'ctx>(
+    context: &'ctx Context,
+    a: &Execution<'ctx>,
+    b: &Execution<'ctx>,
+) -> (usize, Bool<'ctx>, Bool<'ctx>) {
+    let common = a
+        .constraints
+        .iter()
+        .zip(&b.constraints)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = |execution: &Execution<'ctx>| {
+        Bool::and(
+            &context.context,
+            &execution.constraints[common..].iter().collect::<Vec<_>>(),
+        )
+    };
+    (common, suffix(a), suffix(b))
+}
+
+/// Whether two pending executions are at the same point, and merging them keeps the path condition and every merged
+/// value within `max_size` term nodes
+pub fn can_merge<'ctx>(
+    context: &'ctx Context,
+    a: &Execution<'ctx>,
+    b: &Execution<'ctx>,
+    max_size: usize,
+) -> bool {
+    let mergeable = a.status == Status::None
+        && b.status == Status::None
+        && a.cur_block == b.cur_block
+        && a.cur_location.map(|loc| loc.data()) == b.cur_location.map(|loc| loc.data())
+        && a.advance == b.advance
+        && a.widened == b.widened
+        && a.checks.len() == b.checks.len()
+        // Only the checks' constraints can be guarded by the path they came from
+        && !a.checks.iter().chain(&b.checks).any(|check| check.has_pending_state())
+        && a.host_models.iter().all(|(key, model)| {
+            matches!(b.host_models.get(key), Some(other) if model.same_state(other.as_ref()))
+        })
+        && same_shape(&a.state, &b.state);
+    if !mergeable {
+        return false;
+    }
+
+    // Each merged term is an `ite` or `or` node over the parts it combines
+    let (_, on_a, on_b) = path_suffixes(context, a, b);
+    let (on_a, on_b) = (Dynamic::from_ast(&on_a), Dynamic::from_ast(&on_b));
+    term_size(&[&on_a, &on_b], max_size) < max_size
+        && differences(context, &a.state, &b.state)
+            .iter()
+            .all(|(a, b)| term_size(&[&on_a, a, b], max_size) < max_size)
+}
+
+impl<'ctx, 'm> Engine<'ctx, 'm> {
+    /// Resumes the held execution the others are most likely waiting behind, the innermost one: in the deepest
+    /// call, at the earliest location
+    pub(crate) fn release_held(&mut self) -> Option<Execution<'ctx>> {
+        let index = (0..self.held.len()).min_by_key(|&index| {
+            let execution = &self.held[index];
+            (
+                Reverse(execution.state.call_stack.len()),
+                execution.cur_location.map(|loc| loc.data()),
+            )
+        })?;
+        Some(self.held.swap_remove(index))
+    }
+
+    /// Merges two executions that `can_merge`, into one whose path condition is the disjunction of theirs
+    pub fn merge_executions(
+        &self,
+        mut a: Execution<'ctx>,
+        mut b: Execution<'ctx>,
+    ) -> Execution<'ctx> {
+        let context = self.context;
+
+        // Both paths share the constraints from before they forked
+        let (common, on_a, on_b) = path_suffixes(context, &a, &b);
+
+        let mut constraints = a.constraints[..common].to_vec();
+        let either = Bool::or(&context.context, &[&on_a, &on_b]).simplify();
+        if either.as_bool() != Some(true) {
+            constraints.push(either);
+        }
+        a.set_constraints(constraints);
+
+        for (frame, other) in a.state.call_stack.iter_mut().zip(&b.state.call_stack) {
+            for (value, other) in frame.value_stack.iter_mut().zip(&other.value_stack) {
+                *value = merge_value(context, &on_a, value, other);
+            }
+            for (local, value) in frame.locals.iter_mut() {
+                *value = merge_value(context, &on_a, value, &other.locals[local]);
+            }
+            for (block, height) in &other.heights {
+                frame.heights.entry(*block).or_insert(*height);
+            }
+        }
+        for (global, value) in a.state.globals.iter_mut() {
+            *value = merge_value(context, &on_a, value, &b.state.globals[global]);
+        }
+        for (id, memory) in a.state.memories.iter_mut() {
+            let other: &Memory = &b.state.memories[id];
+            memory.size = merge_value(context, &on_a, &memory.size, &other.size);
+            if memory.array != other.array {
+                memory.array = on_a.ite(&memory.array, &other.array);
+            }
+        }
+        for (fd, bytes) in a.state.streams.iter_mut() {
+            for (byte, other) in bytes.iter_mut().zip(&b.state.streams[fd]) {
+                if byte != other {
+                    *byte = on_a.ite(byte, other);
+                }
+            }
+        }
+
+        // A finding recorded on either path must stay tied to that path
+        for (check, other) in a.checks.iter_mut().zip(b.checks.iter_mut()) {
+            let mut merged: HashMap<u32, Bool<'ctx>> = HashMap::new();
+            for (on, constraints) in [
+                (&on_a, check.constraints_mut()),
+                (&on_b, other.constraints_mut()),
+            ] {
+                for (loc, constraint) in constraints.drain() {
+                    let guarded = Bool::and(&context.context, &[on, &constraint]);
+                    let combined = match merged.remove(&loc) {
+                        Some(existing) => Bool::or(&context.context, &[&existing, &guarded]),
+                        None => guarded,
+                    };
+                    merged.insert(loc, combined);
+                }
+            }
+            *check.constraints_mut() = merged;
+        }
+
+        // The merged path stands for both sides, so it is as deep in the fork tree as the shallower one
+        a.forks = a.forks.min(b.forks);
+
+        for (block, hotness) in b.hotness {
+            let entry = a.hotness.entry(block).or_insert(0);
+            *entry = (*entry).max(hotness);
+        }
+
+        a
+    }
+}
@@ -1,5 +1,6 @@
 use crate::checks::CheckResult;
 use crate::context::Context;
+use crate::ffi::float;
 use crate::state::{Execution, Status};
 use crate::value::{SymVal, Val};
 use colored::Colorize;
@@ -25,19 +26,28 @@ impl Reporter {
             format!("Collected {} Execution Paths", executions.len()).blue()
         );
         for execution in executions.iter() {
-            if execution.status == Status::Complete {
-                println!("    {}", execution.to_string().white());
-            } else {
-                println!(
-                    "    ✗ {} {}",
-                    (match execution.status {
-                        Status::Terminated => "Terminated",
-                        _ => todo!(),
-                    })
-                    .yellow(),
-                    execution.to_string().bright_black()
-                );
-            }
+            let failure = match &execution.status {
+                Status::Complete => {
+                    println!("    {}", execution.to_string().white());
+                    continue;
+                }
+                Status::Exited => {
+                    println!("    {} {}", "Exited".white(), execution.to_string().white());
+                    continue;
+                }
+                // Still live when exploration stopped
+                Status::None => String::from("Unfinished"),
+                Status::Terminated => String::from("Terminated"),
+                Status::OutOfBudget(limit) => format!("OutOfBudget({:?})", limit),
+                Status::Trap(reason) => format!("Trap({:?})", reason),
+                Status::Unsupported(what) => format!("Unsupported({})", what),
+                Status::InvalidHostResult(import) => format!("InvalidHostResult({})", import),
+            };
+            println!(
+                "    ✗ {} {}",
+                failure.yellow(),
+                execution.to_string().bright_black()
+            );
         }
     }
 
@@ -52,6 +62,20 @@ impl Reporter {
             .join(", ")
     }
 
+    fn format_value(value: &Val, model: &z3::Model) -> String {
+        match value {
+            Val::Conc(val) => format!("{}", val),
+            Val::Sym(val) => match val {
+                SymVal::I32(bv_val) | SymVal::I64(bv_val) => {
+                    format!("{}", model.eval(bv_val, true).unwrap())
+                }
+                SymVal::F32(float_val) | SymVal::F64(float_val) => {
+                    format!("{}", float::eval(model, float_val))
+                }
+            },
+        }
+    }
+
     pub fn format_model(inputs: &HashMap<ir::LocalId, Val>, model: &z3::Model) -> String {
         inputs
             .iter()
@@ -59,53 +83,87 @@ impl Reporter {
                 format!(
                     "local{}={}",
                     local_id.index(),
-                    match input_value {
-                        Val::Conc(val) => format!("{}", val),
-                        Val::Sym(val) => {
-                            format!(
-                                "{}",
-                                match val {
-                                    SymVal::I32(i32_val) => model.eval(i32_val, true).unwrap(),
-                                }
-                            )
-                        }
-                    }
+                    Self::format_value(input_value, model)
                 )
             })
             .collect::<Vec<_>>()
             .join(", ")
     }
 
+    pub fn format_globals(globals: &HashMap<walrus::GlobalId, Val>, model: &z3::Model) -> String {
+        globals
+            .iter()
+            .map(|(global_id, global_value)| {
+                format!(
+                    "global{}={}",
+                    global_id.index(),
+                    Self::format_value(global_value, model)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Values of the local and global inputs in `model`, for check failures
+    pub fn format_inputs(
+        inputs: &HashMap<ir::LocalId, Val>,
+        globals: &HashMap<walrus::GlobalId, Val>,
+        model: &z3::Model,
+    ) -> String {
+        [
+            Self::format_model(inputs, model),
+            Self::format_globals(globals, model),
+        ]
+        .into_iter()
+        .filter(|values| !values.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+
     pub fn report_checks<'ctx>(
         &self,
         context: &'ctx Context,
         inputs: &HashMap<ir::LocalId, Val<'ctx>>,
+        globals: &HashMap<walrus::GlobalId, Val<'ctx>>,
         executions: &mut Vec<Execution<'ctx>>,
     ) {
         println!("  {}", "Execution Path Checks".blue());
         for execution in executions {
-            let model_input = execution.solve(context);
-            match model_input {
-                None => {
+            let solver = execution.get_solver(context);
+            match context.check_uncounted(&solver) {
+                z3::SatResult::Unsat => {
                     println!(
                         "{}",
                         format!("    #{}: Infeasible; skipping...", execution.id).bright_black()
                     );
                 }
-                Some(model) => {
+                z3::SatResult::Unknown => {
+                    println!(
+                        "{}",
+                        format!("    #{}: Unknown; skipping...", execution.id).bright_black()
+                    );
+                }
+                z3::SatResult::Sat => {
+                    let model = solver.get_model().unwrap();
+                    let global_input = if globals.is_empty() {
+                        String::from("")
+                    } else {
+                        format!("; Globals=[{}]", Self::format_globals(globals, &model))
+                    };
                     println!(
                         "{}",
                         format!(
-                            "    #{}: Feasible; Input=[{}]",
+                            "    #{}: Feasible; Input=[{}]{}",
                             execution.id,
-                            Self::format_model(inputs, &model)
+                            Self::format_model(inputs, &model),
+                            global_input
                         )
                         .white()
                     );
 
                     let mut execution_checks = std::mem::take(&mut execution.checks);
                     for check in &mut execution_checks {
-                        match check.run(context, execution, inputs) {
+                        match check.run(context, execution, inputs, globals) {
                             CheckResult::Ok => {
                                 println!("        {}", format!("[{}] ✓", check.name()).green())
                             }
@@ -0,0 +1,231 @@
+use crate::state::Execution;
+use std::collections::{HashSet, VecDeque};
+
+/// Decides which pending execution the engine steps next
+pub trait SearchStrategy<'ctx>: std::fmt::Debug {
+    fn push(&mut self, execution: Execution<'ctx>);
+
+    fn pop(&mut self) -> Option<Execution<'ctx>>;
+
+    /// Number of pending executions
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes a pending execution for which `predicate` holds, used to find merge candidates
+    fn take(
+        &mut self,
+        predicate: &mut dyn FnMut(&Execution<'ctx>) -> bool,
+    ) -> Option<Execution<'ctx>>;
+
+    /// Whether `predicate` holds for any pending execution
+    fn any(&self, predicate: &mut dyn FnMut(&Execution<'ctx>) -> bool) -> bool;
+}
+
+/// Steps the oldest pending execution first
+#[derive(Debug, Default)]
+pub struct BreadthFirstSearch<'ctx> {
+    executions: VecDeque<Execution<'ctx>>,
+}
+
+impl<'ctx> BreadthFirstSearch<'ctx> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<'ctx> SearchStrategy<'ctx> for BreadthFirstSearch<'ctx> {
+    fn push(&mut self, execution: Execution<'ctx>) {
+        self.executions.push_back(execution);
+    }
+
+    fn pop(&mut self) -> Option<Execution<'ctx>> {
+        self.executions.pop_front()
+    }
+
+    fn len(&self) -> usize {
+        self.executions.len()
+    }
+
+    fn take(
+        &mut self,
+        predicate: &mut dyn FnMut(&Execution<'ctx>) -> bool,
+    ) -> Option<Execution<'ctx>> {
+        let index = self.executions.iter().position(predicate)?;
+        self.executions.remove(index)
+    }
+
+    fn any(&self, predicate: &mut dyn FnMut(&Execution<'ctx>) -> bool) -> bool {
+        self.executions.iter().any(predicate)
+    }
+}
+
+/// Steps the newest pending execution first, following one path to its end before backtracking
+#[derive(Debug, Default)]
+pub struct DepthFirstSearch<'ctx> {
+    executions: Vec<Execution<'ctx>>,
+}
+
+impl<'ctx> DepthFirstSearch<'ctx> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<'ctx> SearchStrategy<'ctx> for DepthFirstSearch<'ctx> {
+    fn push(&mut self, execution: Execution<'ctx>) {
+        self.executions.push(execution);
+    }
+
+    fn pop(&mut self) -> Option<Execution<'ctx>> {
+        self.executions.pop()
+    }
+
+    fn len(&self) -> usize {
+        self.executions.len()
+    }
+
+    fn take(
+        &mut self,
+        predicate: &mut dyn FnMut(&Execution<'ctx>) -> bool,
+    ) -> Option<Execution<'ctx>> {
+        let index = self.executions.iter().position(predicate)?;
+        Some(self.executions.remove(index))
+    }
+
+    fn any(&self, predicate: &mut dyn FnMut(&Execution<'ctx>) -> bool) -> bool {
+        self.executions.iter().any(predicate)
+    }
+}
+
+/// Random-path search: picks a pending execution as a random walk down the tree of forks would, taking each side
+/// of a fork with equal chance, so one that forked `n` times is picked with weight `2^-n`. Paths that forked less
+/// are favoured over those deep in fork-heavy subtrees. The walk is reproducible for a given seed.
+#[derive(Debug)]
+pub struct RandomPathSearch<'ctx> {
+    executions: Vec<Execution<'ctx>>,
+    state: u64,
+}
+
+impl<'ctx> RandomPathSearch<'ctx> {
+    pub fn new(seed: u64) -> Self {
+        RandomPathSearch {
+            executions: Vec::new(),
+            state: seed,
+        }
+    }
+
+    /// splitmix64, which unlike xorshift has no seed it gets stuck on
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+impl<'ctx> SearchStrategy<'ctx> for RandomPathSearch<'ctx> {
+    fn push(&mut self, execution: Execution<'ctx>) {
+        self.executions.push(execution);
+    }
+
+    fn pop(&mut self) -> Option<Execution<'ctx>> {
+        // Weights are relative to the shallowest execution, so that deep trees don't underflow them all to zero
+        let shallowest = self
+            .executions
+            .iter()
+            .map(|execution| execution.forks)
+            .min()?;
+        let weight = |execution: &Execution| 0.5f64.powi((execution.forks - shallowest) as i32);
+        let total = self.executions.iter().map(weight).sum::<f64>();
+
+        let mut point = (self.next() >> 11) as f64 / (1u64 << 53) as f64 * total;
+        let index = self
+            .executions
+            .iter()
+            .position(|execution| {
+                point -= weight(execution);
+                point < 0.0
+            })
+            .unwrap_or(self.executions.len() - 1);
+        Some(self.executions.swap_remove(index))
+    }
+
+    fn len(&self) -> usize {
+        self.executions.len()
+    }
+
+    fn take(
+        &mut self,
+        predicate: &mut dyn FnMut(&Execution<'ctx>) -> bool,
+    ) -> Option<Execution<'ctx>> {
+        let index = self.executions.iter().position(predicate)?;
+        Some(self.executions.swap_remove(index))
+    }
+
+    fn any(&self, predicate: &mut dyn FnMut(&Execution<'ctx>) -> bool) -> bool {
+        self.executions.iter().any(predicate)
+    }
+}
+
+/// Prefers executions about to enter a block that no execution has entered yet, and otherwise searches depth-first
+#[derive(Debug, Default)]
+pub struct CoverageSearch<'ctx> {
+    executions: Vec<Execution<'ctx>>,
+    covered: HashSet<(walrus::FunctionId, walrus::ir::InstrSeqId)>,
+}
+
+impl<'ctx> CoverageSearch<'ctx> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn next_block(
+        execution: &Execution<'ctx>,
+    ) -> Option<(walrus::FunctionId, walrus::ir::InstrSeqId)> {
+        let frame = execution.state.call_stack.last()?;
+        Some((frame.func, execution.cur_block))
+    }
+}
+
+impl<'ctx> SearchStrategy<'ctx> for CoverageSearch<'ctx> {
+    fn push(&mut self, execution: Execution<'ctx>) {
+        self.executions.push(execution);
+    }
+
+    fn pop(&mut self) -> Option<Execution<'ctx>> {
+        let index = self
+            .executions
+            .iter()
+            .rposition(|execution| match Self::next_block(execution) {
+                Some(block) => !self.covered.contains(&block),
+                None => false,
+            })
+            .unwrap_or(self.executions.len().checked_sub(1)?);
+
+        let execution = self.executions.remove(index);
+        if let Some(block) = Self::next_block(&execution) {
+            self.covered.insert(block);
+        }
+        Some(execution)
+    }
+
+    fn len(&self) -> usize {
+        self.executions.len()
+    }
+
+    fn take(
+        &mut self,
+        predicate: &mut dyn FnMut(&Execution<'ctx>) -> bool,
+    ) -> Option<Execution<'ctx>> {
+        let index = self.executions.iter().position(predicate)?;
+        Some(self.executions.remove(index))
+    }
+
+    fn any(&self, predicate: &mut dyn FnMut(&Execution<'ctx>) -> bool) -> bool {
+        self.executions.iter().any(predicate)
+    }
+}
@@ -1,9 +1,13 @@
+use crate::budget::Limit;
 use crate::checks::Check;
 use crate::context::Context;
 use crate::flow::Loc;
+use crate::host::HostModel;
 use crate::memory::Memory;
+use crate::table::Table;
 use crate::value::Val;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use walrus::ir;
 use z3::ast::Ast;
@@ -11,14 +15,36 @@ use z3::ast::Ast;
 #[derive(Debug, Clone, PartialEq)]
 pub enum TrapReason {
     DivisionByZero,
+    IntegerOverflow,
+    InvalidConversionToInteger,
+    /// `call_indirect` on an undefined or null table entry, or one with the wrong signature
+    InvalidIndirectCall,
+    /// Active element segment that doesn't fit in its table, which fails instantiation
+    TableOutOfBounds,
+    /// Load, store or bulk memory operation outside the memory, or `memory.init` outside its data segment
+    MemoryOutOfBounds,
+    /// Atomic access to an address that isn't a multiple of its size
+    UnalignedAtomic,
+    /// `memory.atomic.wait` on a memory that isn't shared
+    ExpectedSharedMemory,
+    /// Reached an `unreachable` instruction, which is what panics and failed assertions compile to
+    Unreachable,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Status {
     None,
     Complete,
+    /// Ended by the program itself before returning, e.g. through WASI's `proc_exit`
+    Exited,
     Trap(TrapReason),
     Terminated,
+    /// Stopped unfinished because a budget ran out
+    OutOfBudget(Limit),
+    /// Stopped at something the engine doesn't model, such as a vector or reference value
+    Unsupported(String),
+    /// Ended by a host model whose results don't match the signature of the import it models
+    InvalidHostResult(String),
 }
 
 #[derive(Debug, Clone)]
@@ -27,6 +53,7 @@ pub struct Frame<'ctx> {
     pub ret: Option<Loc>,
     pub value_stack: Vec<Val<'ctx>>,
     pub locals: HashMap<ir::LocalId, Val<'ctx>>,
+    pub heights: HashMap<ir::InstrSeqId, usize>, // stack height below the params of each entered block
 }
 
 impl<'ctx> Frame<'ctx> {
@@ -36,6 +63,7 @@ impl<'ctx> Frame<'ctx> {
             ret,
             value_stack: Vec::new(),
             locals: HashMap::new(),
+            heights: HashMap::new(),
         }
     }
 }
@@ -64,14 +92,28 @@ impl<'ctx> std::fmt::Display for Frame<'ctx> {
 #[derive(Debug, Clone)]
 pub struct State<'ctx> {
     pub call_stack: Vec<Frame<'ctx>>,
-    pub memory: Option<Memory<'ctx>>,
+    pub memories: HashMap<walrus::MemoryId, Memory<'ctx>>,
+    pub globals: HashMap<walrus::GlobalId, Val<'ctx>>,
+    pub data: HashMap<walrus::DataId, Rc<[u8]>>, // segments available to memory.init
+    pub tables: HashMap<walrus::TableId, Table>,
+    pub streams: HashMap<u32, Vec<z3::ast::BV<'ctx>>>, // bytes written to each file descriptor
+}
+
+impl<'ctx> Default for State<'ctx> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'ctx> State<'ctx> {
     pub fn new() -> Self {
         State {
             call_stack: Vec::new(),
-            memory: None,
+            memories: HashMap::new(),
+            globals: HashMap::new(),
+            data: HashMap::new(),
+            tables: HashMap::new(),
+            streams: HashMap::new(),
         }
     }
 
@@ -90,12 +132,15 @@ impl<'ctx> State<'ctx> {
                     _ => (),
                 }
             }
+        }
 
-            match &mut self.memory {
-                Some(memory) => {
-                    memory.array = memory.array.simplify();
-                }
-                None => (),
+        for memory in self.memories.values_mut() {
+            memory.array = memory.array.simplify();
+        }
+
+        for global in self.globals.values_mut() {
+            if let Val::Sym(val) = global {
+                val.simplify();
             }
         }
     }
@@ -105,17 +150,29 @@ impl<'ctx> std::fmt::Display for State<'ctx> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{{[{}]{}}}",
+            "{{[{}]{}{}}}",
             self.call_stack
                 .iter()
                 .rev()
                 .map(|v| v.to_string())
                 .collect::<Vec<_>>()
                 .join(", "),
-            match &self.memory {
-                None => String::from(""),
-                Some(memory) => format!(", memory={:?}", memory),
-            }
+            if self.globals.is_empty() {
+                String::from("")
+            } else {
+                format!(
+                    ", globals=[{}]",
+                    self.globals
+                        .iter()
+                        .map(|(k, v)| format!("#{}={}", k.index(), v))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            },
+            self.memories
+                .iter()
+                .map(|(k, v)| format!(", memory#{}={:?}", k.index(), v))
+                .collect::<String>()
         )
     }
 }
@@ -125,13 +182,20 @@ pub struct Execution<'ctx> {
     pub id: usize,
     pub state: State<'ctx>,
     pub constraints: Vec<z3::ast::Bool<'ctx>>,
+    /// Incremental solver over `constraints`, only shared by plain clones until one of them adds a constraint
+    solver: Option<Rc<z3::Solver<'ctx>>>,
     pub cur_block: ir::InstrSeqId,
     pub cur_location: Option<ir::InstrLocId>, // None if start of block
     /// Tells the engine to advance an extra instruction, used to skip past the `call` instruction when returning
     pub advance: bool,
     pub status: Status,
     pub checks: Vec<Box<dyn Check<'ctx> + 'ctx>>,
+    pub host_models: HashMap<(String, String), Box<dyn HostModel<'ctx> + 'ctx>>,
     pub hotness: HashMap<ir::InstrSeqId, usize>,
+    /// Loops whose state has been forgotten, where going round again ends the path
+    pub widened: HashSet<ir::InstrSeqId>,
+    /// Forks on the path since its entry, which is its depth in the tree of forks
+    pub forks: usize,
 }
 
 static EXECUTION_COUNTER: AtomicUsize = AtomicUsize::new(0);
@@ -141,13 +205,17 @@ impl<'ctx> Execution<'ctx> {
         Execution {
             id: EXECUTION_COUNTER.fetch_add(1, Ordering::SeqCst),
             constraints: Vec::new(),
+            solver: None,
             state,
             cur_block: entry,
             cur_location: None,
             advance: false,
             status: Status::None,
             checks: Vec::new(),
+            host_models: HashMap::new(),
             hotness: HashMap::new(),
+            widened: HashSet::new(),
+            forks: 0,
         }
     }
 
@@ -155,14 +223,49 @@ impl<'ctx> Execution<'ctx> {
         self.checks.push(check);
     }
 
-    pub fn from(other: &Execution<'ctx>) -> Self {
+    /// Copy of `other` for the other side of a fork, with a solver of its own so that both sides stay incremental.
+    /// Both sides count the fork.
+    pub fn from(other: &mut Execution<'ctx>, context: &'ctx Context) -> Self {
+        other.forks += 1;
         let mut new_execution = other.clone();
         new_execution.id = EXECUTION_COUNTER.fetch_add(1, Ordering::SeqCst);
+        new_execution.solver = other
+            .solver
+            .as_ref()
+            .map(|solver| Rc::new(context.copy_solver(solver)));
         new_execution
     }
 
+    pub fn add_constraint(&mut self, constraint: z3::ast::Bool<'ctx>) {
+        match self.solver.as_mut().and_then(Rc::get_mut) {
+            Some(solver) => solver.assert(&constraint),
+            None => self.solver = None,
+        }
+        self.constraints.push(constraint);
+    }
+
+    /// Replaces the path condition, e.g. with the disjunction of two merged paths
+    pub fn set_constraints(&mut self, constraints: Vec<z3::ast::Bool<'ctx>>) {
+        self.constraints = constraints;
+        self.solver = None;
+    }
+
+    /// Whether the path can continue with `condition` holding, counting an undecided query as feasible
+    pub fn is_feasible(&mut self, context: &'ctx Context, condition: &z3::ast::Bool<'ctx>) -> bool {
+        if self.solver.is_none() {
+            self.solver = Some(Rc::new(self.get_solver(context)));
+        }
+        let solver = self.solver.as_ref().unwrap();
+
+        solver.push();
+        solver.assert(condition);
+        let result = context.check(solver);
+        solver.pop(1);
+        result != z3::SatResult::Unsat
+    }
+
     pub fn get_solver(&self, context: &'ctx Context) -> z3::Solver<'ctx> {
-        let solver = z3::Solver::new(&context.context);
+        let solver = context.solver();
         for constraint in self.constraints.iter() {
             solver.assert(constraint);
         }
@@ -171,10 +274,10 @@ impl<'ctx> Execution<'ctx> {
 
     pub fn solve(&self, context: &'ctx Context) -> Option<z3::Model<'ctx>> {
         let solver = self.get_solver(context);
-        if solver.check() == z3::SatResult::Unsat {
-            None
-        } else {
+        if context.check_uncounted(&solver) == z3::SatResult::Sat {
             solver.get_model()
+        } else {
+            None
         }
     }
 }
@@ -0,0 +1,74 @@
+use crate::{
+    engine::Engine,
+    state::{State, TrapReason},
+    value::Val,
+};
+use log::warn;
+
+pub type Table = Vec<Option<walrus::FunctionId>>;
+
+impl<'ctx, 'm> Engine<'ctx, 'm> {
+    /// Creates every table with its initial size and applies the active element segments, failing instantiation with
+    /// a trap when a segment doesn't fit in its table
+    pub fn init_tables(&self, state: &mut State<'ctx>) -> Result<(), TrapReason> {
+        for table in self.context.module.tables.iter() {
+            state
+                .tables
+                .insert(table.id(), vec![None; table.initial as usize]);
+        }
+
+        for element in self.context.module.elements.iter() {
+            let (table_id, offset) = match element.kind {
+                walrus::ElementKind::Active { table, offset } => (table, offset),
+                _ => continue,
+            };
+            let offset = match offset {
+                walrus::InitExpr::Value(walrus::ir::Value::I32(offset)) => offset as u32,
+                walrus::InitExpr::Global(global) => match state.globals.get(&global).unwrap() {
+                    Val::Conc(val) => val.as_i32() as u32,
+                    // Tables are only indexed concretely
+                    Val::Sym(_) => {
+                        warn!(
+                            "Skipping element segment {} with a symbolic offset",
+                            element.id().index()
+                        );
+                        continue;
+                    }
+                },
+                _ => unreachable!(),
+            } as usize;
+
+            let table = state.tables.get_mut(&table_id).unwrap();
+            let end = offset + element.members.len();
+            if end > table.len() {
+                return Err(TrapReason::TableOutOfBounds);
+            }
+            table[offset..end].copy_from_slice(&element.members);
+        }
+        Ok(())
+    }
+
+    /// Whether `func` can be called through a `call_indirect` expecting type `ty`
+    pub fn is_compatible(&self, func: walrus::FunctionId, ty: walrus::TypeId) -> bool {
+        let types = &self.context.module.types;
+        let func_ty = types.get(self.context.module.funcs.get(func).ty());
+        let expected_ty = types.get(ty);
+        func_ty.params() == expected_ty.params() && func_ty.results() == expected_ty.results()
+    }
+
+    /// Entries of a table that can be called with type `ty`, along with their index
+    pub fn get_call_targets(
+        &self,
+        table: &Table,
+        ty: walrus::TypeId,
+    ) -> Vec<(usize, walrus::FunctionId)> {
+        table
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| match entry {
+                Some(func) if self.is_compatible(*func, ty) => Some((index, *func)),
+                _ => None,
+            })
+            .collect()
+    }
+}
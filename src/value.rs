@@ -1,3 +1,4 @@
+use crate::ffi::float;
 use walrus::ir;
 use z3::ast::Ast;
 
@@ -14,6 +15,24 @@ impl<'ctx> Val<'ctx> {
             Val::Conc(val) => SymVal::from_concrete(context, val),
         }
     }
+
+    pub fn ty(&self) -> walrus::ValType {
+        match self {
+            Val::Sym(SymVal::I32(_)) | Val::Conc(ConcVal(ir::Value::I32(_))) => {
+                walrus::ValType::I32
+            }
+            Val::Sym(SymVal::I64(_)) | Val::Conc(ConcVal(ir::Value::I64(_))) => {
+                walrus::ValType::I64
+            }
+            Val::Sym(SymVal::F32(_)) | Val::Conc(ConcVal(ir::Value::F32(_))) => {
+                walrus::ValType::F32
+            }
+            Val::Sym(SymVal::F64(_)) | Val::Conc(ConcVal(ir::Value::F64(_))) => {
+                walrus::ValType::F64
+            }
+            Val::Conc(ConcVal(ir::Value::V128(_))) => walrus::ValType::V128,
+        }
+    }
 }
 
 impl<'ctx> std::fmt::Display for Val<'ctx> {
@@ -32,7 +51,10 @@ impl std::fmt::Display for ConcVal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.0 {
             ir::Value::I32(value) => write!(f, "{value}: i32"),
-            _ => todo!(),
+            ir::Value::I64(value) => write!(f, "{value}: i64"),
+            ir::Value::F32(value) => write!(f, "{value}: f32"),
+            ir::Value::F64(value) => write!(f, "{value}: f64"),
+            ir::Value::V128(value) => write!(f, "{value:#x}: v128"),
         }
     }
 }
@@ -41,7 +63,11 @@ impl ConcVal {
     pub fn from_valtype(val_type: walrus::ValType) -> ConcVal {
         match val_type {
             walrus::ValType::I32 => ConcVal(ir::Value::I32(0)),
-            _ => todo!(),
+            walrus::ValType::I64 => ConcVal(ir::Value::I64(0)),
+            walrus::ValType::F32 => ConcVal(ir::Value::F32(0.0)),
+            walrus::ValType::F64 => ConcVal(ir::Value::F64(0.0)),
+            // The engine checks types with `is_modelled` before creating values
+            _ => unreachable!("{} values are not modelled", val_type),
         }
     }
 }
@@ -49,12 +75,18 @@ impl ConcVal {
 #[derive(Debug, Clone)]
 pub enum SymVal<'ctx> {
     I32(z3::ast::BV<'ctx>),
+    I64(z3::ast::BV<'ctx>),
+    F32(z3::ast::Float<'ctx>),
+    F64(z3::ast::Float<'ctx>),
 }
 
 impl<'ctx> std::fmt::Display for SymVal<'ctx> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             SymVal::I32(value) => write!(f, "{:?}: i32", value),
+            SymVal::I64(value) => write!(f, "{:?}: i64", value),
+            SymVal::F32(value) => write!(f, "{:?}: f32", value),
+            SymVal::F64(value) => write!(f, "{:?}: f64", value),
         }
     }
 }
@@ -63,6 +95,17 @@ impl<'ctx> SymVal<'ctx> {
     pub fn from_concrete(context: &'ctx z3::Context, value: &ConcVal) -> SymVal<'ctx> {
         match value.0 {
             ir::Value::I32(value) => SymVal::I32(z3::ast::BV::from_i64(context, value as i64, 32)),
+            ir::Value::I64(value) => SymVal::I64(z3::ast::BV::from_i64(context, value, 64)),
+            ir::Value::F32(value) => SymVal::F32(float::from_bits(&z3::ast::BV::from_u64(
+                context,
+                value.to_bits() as u64,
+                32,
+            ))),
+            ir::Value::F64(value) => SymVal::F64(float::from_bits(&z3::ast::BV::from_u64(
+                context,
+                value.to_bits(),
+                64,
+            ))),
             _ => unreachable!(),
         }
     }
@@ -74,19 +117,61 @@ impl<'ctx> SymVal<'ctx> {
     ) -> SymVal<'ctx> {
         match val_type {
             walrus::ValType::I32 => SymVal::I32(z3::ast::BV::new_const(context, name, 32)),
-            _ => todo!(),
+            walrus::ValType::I64 => SymVal::I64(z3::ast::BV::new_const(context, name, 64)),
+            walrus::ValType::F32 => SymVal::F32(z3::ast::Float::new_const_float32(context, name)),
+            walrus::ValType::F64 => SymVal::F64(z3::ast::Float::new_const_double(context, name)),
+            // The engine checks types with `is_modelled` before creating values
+            _ => unreachable!("{} values are not modelled", val_type),
+        }
+    }
+
+    /// Builds `if cond { then } else { otherwise }` over two values of the same type
+    pub fn ite(
+        cond: &z3::ast::Bool<'ctx>,
+        then: &SymVal<'ctx>,
+        otherwise: &SymVal<'ctx>,
+    ) -> SymVal<'ctx> {
+        match (then, otherwise) {
+            (SymVal::I32(then), SymVal::I32(otherwise)) => SymVal::I32(cond.ite(then, otherwise)),
+            (SymVal::I64(then), SymVal::I64(otherwise)) => SymVal::I64(cond.ite(then, otherwise)),
+            (SymVal::F32(then), SymVal::F32(otherwise)) => SymVal::F32(cond.ite(then, otherwise)),
+            (SymVal::F64(then), SymVal::F64(otherwise)) => SymVal::F64(cond.ite(then, otherwise)),
+            _ => panic!("ite on mismatched types"),
         }
     }
 
     pub fn simplify(&mut self) {
         match self {
-            SymVal::I32(val) => *val = val.simplify(),
+            SymVal::I32(val) | SymVal::I64(val) => *val = val.simplify(),
+            SymVal::F32(val) | SymVal::F64(val) => *val = val.simplify(),
         }
     }
 
     pub fn as_i32(&self) -> &z3::ast::BV<'ctx> {
         match self {
             SymVal::I32(z3_val) => z3_val,
+            _ => panic!("not i32"),
+        }
+    }
+
+    pub fn as_i64(&self) -> &z3::ast::BV<'ctx> {
+        match self {
+            SymVal::I64(z3_val) => z3_val,
+            _ => panic!("not i64"),
+        }
+    }
+
+    pub fn as_f32(&self) -> &z3::ast::Float<'ctx> {
+        match self {
+            SymVal::F32(z3_val) => z3_val,
+            _ => panic!("not f32"),
+        }
+    }
+
+    pub fn as_f64(&self) -> &z3::ast::Float<'ctx> {
+        match self {
+            SymVal::F64(z3_val) => z3_val,
+            _ => panic!("not f64"),
         }
     }
 }
@@ -99,4 +184,28 @@ impl ConcVal {
             panic!("not i32")
         }
     }
+
+    pub fn as_i64(&self) -> i64 {
+        if let ir::Value::I64(value) = self.0 {
+            value
+        } else {
+            panic!("not i64")
+        }
+    }
+
+    pub fn as_f32(&self) -> f32 {
+        if let ir::Value::F32(value) = self.0 {
+            value
+        } else {
+            panic!("not f32")
+        }
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        if let ir::Value::F64(value) = self.0 {
+            value
+        } else {
+            panic!("not f64")
+        }
+    }
 }
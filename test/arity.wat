@@ -0,0 +1,51 @@
+(module
+    (type $pair (func (param i32) (result i32 i32)))
+
+    (func $unwind (param $x i32) (result i32)
+        i32.const 7
+        block $b (result i32)
+            i32.const 1
+            i32.const 2
+            local.get $x
+            br $b
+        end
+        i32.div_u
+    )
+
+    (func $multi (param $x i32) (result i32)
+        local.get $x
+        block $b (type $pair)
+            i32.const 5
+            i32.const 6
+            i32.const 0
+            br_if $b
+            drop
+            drop
+            i32.const 1
+        end
+        i32.sub
+        i32.const 100
+        i32.div_u
+    )
+
+    (func $countdown (param $n i32) (result i32)
+        i32.const 1
+        i32.const 3
+        block $exit
+            loop $again
+                i32.const 42
+                local.get $n
+                i32.eqz
+                br_if $exit
+                local.get $n
+                i32.const 1
+                i32.sub
+                local.set $n
+                br $again
+            end
+        end
+        i32.const 3
+        i32.sub
+        i32.div_u
+    )
+)
@@ -0,0 +1,40 @@
+(module
+    (memory 1 1 shared)
+
+    ;; increments a counter and returns the value it had before
+    (func $increment (param $addr i32) (result i32)
+        local.get $addr
+        i32.const 1
+        i32.atomic.rmw.add
+    )
+    (export "increment" (func $increment))
+
+    ;; traps when $addr isn't aligned to 4 bytes
+    (func $swap (param $addr i32) (param $expected i32) (result i32)
+        local.get $addr
+        local.get $expected
+        i32.const 42
+        i32.atomic.rmw.cmpxchg
+        i32.const 42
+        i32.eq
+        if
+            unreachable
+        end
+        i32.const 0
+    )
+    (export "swap" (func $swap))
+
+    ;; returns 1 when the value differs from $expected, 2 after a time-out
+    (func $wait (param $expected i32) (result i32)
+        atomic.fence
+        i32.const 0
+        local.get $expected
+        i64.const 0
+        memory.atomic.wait32
+        i32.const 0
+        i32.const 1
+        memory.atomic.notify
+        i32.add
+    )
+    (export "wait" (func $wait))
+)
@@ -0,0 +1,15 @@
+(module
+    ;; targets 0 and 2 both leave the function: one through the function label, the other through a block with
+    ;; nothing after it
+    (func $exits (param $x i32) (result i32)
+        block $tail (result i32)
+            i32.const 1
+            block $inner (result i32)
+                i32.const 2
+                local.get $x
+                br_table 2 0 1
+            end
+            drop
+        end
+    )
+)
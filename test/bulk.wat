@@ -0,0 +1,61 @@
+(module
+    (memory 1)
+    (data $digits "\01\02\03\00")
+
+    (func $fill (param $len i32) (result i32)
+        i32.const 100
+        i32.const 0
+        local.get $len
+        memory.fill
+        i32.const 7
+        i32.const 102
+        i32.load8_u
+        i32.div_u
+    )
+
+    (func $fill_concrete (result i32)
+        i32.const 100
+        i32.const 0
+        i32.const 8
+        memory.fill
+        i32.const 7
+        i32.const 107
+        i32.load8_u
+        i32.div_u
+    )
+
+    (func $copy (param $len i32) (result i32)
+        i32.const 200
+        i32.const 255
+        i32.const 4
+        memory.fill
+        i32.const 201
+        i32.const 200
+        local.get $len
+        memory.copy
+        i32.const 7
+        i32.const 202
+        i32.load8_u
+        i32.div_u
+    )
+
+    (func $init (param $src i32) (result i32)
+        i32.const 300
+        local.get $src
+        i32.const 1
+        memory.init $digits
+        i32.const 7
+        i32.const 300
+        i32.load8_u
+        i32.div_u
+    )
+
+    (func $dropped (result i32)
+        data.drop $digits
+        i32.const 300
+        i32.const 0
+        i32.const 1
+        memory.init $digits
+        i32.const 0
+    )
+)
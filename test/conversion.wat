@@ -0,0 +1,26 @@
+(module
+  (func $trunc (param $x f64) (result i32)
+    local.get $x
+    i32.trunc_f64_s)
+  (func $sat (param $x f32) (result i32)
+    local.get $x
+    i32.trunc_sat_f32_u
+    i32.const 100
+    i32.gt_u)
+  (func $convert (param $a i64) (result f32)
+    local.get $a
+    i32.wrap_i64
+    i64.extend_i32_u
+    f64.convert_i64_u
+    f32.demote_f64)
+  (func $conctrap (result i32)
+    f64.const 3e10
+    i32.trunc_f64_u)
+  (func $concok (result i64)
+    f32.const -7.9
+    i64.trunc_f32_s
+    i32.const -2147483648
+    i32.const -1
+    i32.div_s
+    drop)
+)
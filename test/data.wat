@@ -0,0 +1,23 @@
+(module
+    (import "env" "__memory_base" (global $base i32))
+    (memory 1)
+    (data (i32.const 16) "\01\02\00\04")
+    (data (global.get $base) "\07")
+    (data $passive "hello")
+
+    (func $table (param $i i32) (result i32)
+        i32.const 100
+        i32.const 16
+        local.get $i
+        i32.const 3
+        i32.and
+        i32.add
+        i32.load8_u
+        i32.div_u
+    )
+
+    (func $relative (result i32)
+        global.get $base
+        i32.load8_u
+    )
+)
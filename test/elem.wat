@@ -0,0 +1,11 @@
+(module
+    ;; the segment runs past the end of the table, so instantiation traps instead of growing it
+    (table 2 funcref)
+    (elem (i32.const 1) $one $two)
+    (func $one (result i32)
+        i32.const 1
+    )
+    (func $two (result i32)
+        i32.const 2
+    )
+)
@@ -0,0 +1,30 @@
+(module
+    (memory 1)
+    (global $divisor (mut i32) (i32.const 0))
+
+    (func $init
+        i32.const 4
+        global.set $divisor
+        i32.const 0
+        i32.const 2
+        i32.store
+    )
+    (start $init)
+
+    (func $divide (param $x i32) (result i32)
+        local.get $x
+        global.get $divisor
+        i32.div_u
+        i32.const 0
+        i32.load
+        i32.div_u
+    )
+    (export "divide" (func $divide))
+
+    (func $_start
+        i32.const 8
+        call $divide
+        drop
+    )
+    (export "_start" (func $_start))
+)
@@ -0,0 +1,36 @@
+(module
+    (import "wasi_snapshot_preview1" "environ_sizes_get" (func $environ_sizes_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "environ_get" (func $environ_get (param i32 i32) (result i32)))
+    ;; declared with one param too few, so it is not modelled
+    (import "wasi_snapshot_preview1" "args_get" (func $args_get (param i32) (result i32)))
+    (memory 1)
+
+    ;; divides by zero when the first variable starts with `A`
+    (func $first_variable (result i32)
+        i32.const 0
+        i32.const 64
+        call $environ_get
+        drop
+        i32.const 100
+        i32.const 0
+        i32.load
+        i32.load8_u
+        i32.const 65
+        i32.sub
+        i32.div_u
+    )
+
+    (func $count (result i32)
+        i32.const 0
+        i32.const 4
+        call $environ_sizes_get
+        drop
+        i32.const 0
+        i32.load
+    )
+
+    (func $bad_args (result i32)
+        i32.const 0
+        call $args_get
+    )
+)
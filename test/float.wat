@@ -0,0 +1,38 @@
+(module
+  (memory 1)
+  (func $fcmp (param $x f32) (param $y f32) (result i32)
+    local.get $x
+    local.get $y
+    f32.mul
+    f32.const 6.5
+    f32.eq)
+  (func $fsqrt (param $x f64) (result i32)
+    local.get $x
+    f64.sqrt
+    f64.const 3.0
+    f64.gt
+    if (result i32)
+      local.get $x
+      f64.nearest
+      f64.const 10.0
+      f64.eq
+    else
+      i32.const 7
+    end)
+  (func $fmem (param $x f64) (result f64)
+    i32.const 0
+    local.get $x
+    f64.store
+    i32.const 0
+    i64.load
+    f64.reinterpret_i64
+    f64.const -0.0
+    f64.min)
+  (func $conc (result f32)
+    f32.const 2.5
+    f32.nearest
+    f32.const -0.0
+    f32.const 0.0
+    f32.max
+    f32.copysign)
+)
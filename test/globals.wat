@@ -0,0 +1,20 @@
+(module
+  (global $counter (mut i32) (i32.const 0))
+  (global $limit i32 (i32.const 10))
+  (export "counter" (global $counter))
+  (func $bump (param $x i32) (result i32)
+    global.get $counter
+    local.get $x
+    i32.add
+    global.set $counter
+    global.get $counter
+    global.get $limit
+    i32.div_u)
+  (func $guarded (param $x i32) (result i32)
+    global.get $limit
+    global.get $counter
+    local.get $x
+    i32.sub
+    i32.div_s)
+  (export "bump" (func $bump))
+  (export "guarded" (func $guarded)))
@@ -0,0 +1,60 @@
+(module
+    (memory 1 4)
+
+    (func $unchecked (param $pages i32) (result i32)
+        local.get $pages
+        memory.grow
+        drop
+        memory.size
+    )
+
+    (func $checked (param $pages i32) (result i32)
+        local.get $pages
+        memory.grow
+        i32.const -1
+        i32.eq
+        if (result i32)
+            i32.const 0
+        else
+            memory.size
+        end
+    )
+
+    (func $fits (result i32)
+        i32.const 3
+        memory.grow
+        drop
+        i32.const 1
+        memory.grow
+    )
+
+    ;; saved but never compared
+    (func $saved (param $pages i32) (result i32) (local $old i32)
+        local.get $pages
+        memory.grow
+        local.set $old
+        memory.size
+    )
+
+    (func $saved_checked (param $pages i32) (result i32) (local $old i32)
+        local.get $pages
+        memory.grow
+        local.set $old
+        i32.const -1
+        local.get $old
+        i32.ne
+    )
+
+    ;; overwritten before being compared
+    (func $overwritten (param $pages i32) (result i32) (local $old i32)
+        local.get $pages
+        memory.grow
+        local.tee $old
+        drop
+        i32.const 0
+        local.set $old
+        local.get $old
+        i32.const -1
+        i32.eq
+    )
+)
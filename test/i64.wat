@@ -0,0 +1,21 @@
+(module
+  (memory 1)
+  (func $mem (param $a i64) (param $d i64) (result i64)
+    i32.const 8
+    local.get $a
+    i64.store
+    i32.const 8
+    i64.load32_u
+    i64.clz
+    local.get $d
+    i64.div_u)
+  (func $shifts (result i64)
+    i64.const 1
+    i64.const 65
+    i64.shl
+    i64.popcnt
+    i64.const -1
+    i64.const 3
+    i64.rem_s
+    i64.add)
+)
@@ -0,0 +1,13 @@
+(module
+    (import "env" "handle" (global $handle externref))
+    (import "env" "base" (global $base i32))
+    ;; initialized from an import, so it is as unknown as the import itself
+    (global $start i32 (global.get $base))
+    (global $empty externref (ref.null extern))
+
+    (func $offset (param $x i32) (result i32)
+        local.get $x
+        global.get $start
+        i32.div_u
+    )
+)
@@ -0,0 +1,12 @@
+(module
+    (import "env" "get_divisor" (func $get_divisor (result i32)))
+    (import "env" "log" (func $log (param i32)))
+
+    (func $use_import (param $x i32) (result i32)
+        local.get $x
+        call $log
+        local.get $x
+        call $get_divisor
+        i32.div_u
+    )
+)
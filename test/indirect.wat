@@ -0,0 +1,43 @@
+(module
+    (type $binop (func (param i32 i32) (result i32)))
+    (type $unop (func (param i32) (result i32)))
+    (table 5 funcref)
+    (elem (i32.const 0) $div $add $neg)
+
+    (func $div (param $a i32) (param $b i32) (result i32)
+        local.get $a
+        local.get $b
+        i32.div_u
+    )
+
+    (func $add (param $a i32) (param $b i32) (result i32)
+        local.get $a
+        local.get $b
+        i32.add
+    )
+
+    (func $neg (param $a i32) (result i32)
+        i32.const 0
+        local.get $a
+        i32.sub
+    )
+
+    (func $dispatch (param $op i32) (param $x i32) (result i32)
+        i32.const 10
+        local.get $x
+        local.get $op
+        call_indirect (type $binop)
+    )
+
+    (func $concrete (param $x i32) (result i32)
+        local.get $x
+        i32.const 2
+        call_indirect (type $unop)
+    )
+
+    (func $null (param $x i32) (result i32)
+        local.get $x
+        i32.const 3
+        call_indirect (type $unop)
+    )
+)
@@ -0,0 +1,214 @@
+(module
+    ;; every result is compared with what the spec gives, so any reachable `unreachable` is a wrong result
+    (func $concrete
+        ;; rem_s takes the sign of the dividend
+        i32.const -7
+        i32.const 2
+        i32.rem_s
+        i32.const -1
+        i32.ne
+        if
+            unreachable
+        end
+        i32.const -7
+        i32.const 2
+        i32.rem_u
+        i32.const 1
+        i32.ne
+        if
+            unreachable
+        end
+        i32.const 0x0f0
+        i32.const 0x00f
+        i32.or
+        i32.const 255
+        i32.ne
+        if
+            unreachable
+        end
+        i32.const 3
+        i32.const 4
+        i32.ne
+        i32.const 1
+        i32.ne
+        if
+            unreachable
+        end
+        i32.const -1
+        i32.const 1
+        i32.lt_u
+        i32.const 0
+        i32.ne
+        if
+            unreachable
+        end
+        i32.const 1
+        i32.clz
+        i32.const 31
+        i32.ne
+        if
+            unreachable
+        end
+        i32.const 0
+        i32.clz
+        i32.const 32
+        i32.ne
+        if
+            unreachable
+        end
+        i32.const 8
+        i32.ctz
+        i32.const 3
+        i32.ne
+        if
+            unreachable
+        end
+        i32.const 0
+        i32.ctz
+        i32.const 32
+        i32.ne
+        if
+            unreachable
+        end
+        i32.const 0xff
+        i32.popcnt
+        i32.const 8
+        i32.ne
+        if
+            unreachable
+        end
+        i32.const 0x80
+        i32.extend8_s
+        i32.const -128
+        i32.ne
+        if
+            unreachable
+        end
+        i32.const 0x18000
+        i32.extend16_s
+        i32.const -32768
+        i32.ne
+        if
+            unreachable
+        end
+        ;; shift amounts are taken modulo 32
+        i32.const 1
+        i32.const 33
+        i32.shl
+        i32.const 2
+        i32.ne
+        if
+            unreachable
+        end
+        i32.const -8
+        i32.const 33
+        i32.shr_s
+        i32.const -4
+        i32.ne
+        if
+            unreachable
+        end
+        i32.const -8
+        i32.const 32
+        i32.shr_u
+        i32.const -8
+        i32.ne
+        if
+            unreachable
+        end
+    )
+
+    (func $symbolic (param $x i32)
+        ;; shift amounts are taken modulo 32
+        local.get $x
+        i32.const 33
+        i32.shl
+        local.get $x
+        i32.const 1
+        i32.shl
+        i32.eq
+        i32.const 1
+        i32.ne
+        if
+            unreachable
+        end
+        local.get $x
+        i32.const 32
+        i32.shr_u
+        local.get $x
+        i32.eq
+        i32.const 1
+        i32.ne
+        if
+            unreachable
+        end
+        local.get $x
+        i32.const 63
+        i32.shr_s
+        local.get $x
+        i32.const 31
+        i32.shr_s
+        i32.eq
+        i32.const 1
+        i32.ne
+        if
+            unreachable
+        end
+        local.get $x
+        i32.const 4
+        i32.rem_u
+        i32.const 4
+        i32.lt_u
+        i32.const 1
+        i32.ne
+        if
+            unreachable
+        end
+        local.get $x
+        i32.popcnt
+        i32.const 33
+        i32.lt_u
+        i32.const 1
+        i32.ne
+        if
+            unreachable
+        end
+        ;; only zero has 32 leading zeros
+        local.get $x
+        i32.clz
+        i32.const 32
+        i32.eq
+        local.get $x
+        i32.eqz
+        i32.eq
+        i32.const 1
+        i32.ne
+        if
+            unreachable
+        end
+        local.get $x
+        i32.extend8_s
+        i32.const 0xff
+        i32.and
+        local.get $x
+        i32.const 0xff
+        i32.and
+        i32.eq
+        i32.const 1
+        i32.ne
+        if
+            unreachable
+        end
+        local.get $x
+        i32.const 0x8000
+        i32.or
+        i32.extend16_s
+        i32.const 0
+        i32.lt_s
+        i32.const 1
+        i32.ne
+        if
+            unreachable
+        end
+    )
+)
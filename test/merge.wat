@@ -0,0 +1,40 @@
+(module
+    ;; four sequential ifs give 16 paths, or one when merged
+    (func $count (param $a i32) (param $b i32) (param $c i32) (param $d i32) (result i32)
+        (local $n i32)
+        local.get $a
+        if
+            local.get $n
+            i32.const 1
+            i32.add
+            local.set $n
+        end
+        local.get $b
+        if
+            local.get $n
+            i32.const 1
+            i32.add
+            local.set $n
+        end
+        local.get $c
+        if
+            local.get $n
+            i32.const 1
+            i32.add
+            local.set $n
+        end
+        local.get $d
+        if
+            local.get $n
+            i32.const 1
+            i32.add
+            local.set $n
+        end
+        ;; divides by zero only when all four are set
+        i32.const 1
+        i32.const 4
+        local.get $n
+        i32.sub
+        i32.div_u
+    )
+)
@@ -0,0 +1,27 @@
+(module
+    (memory $small 1)
+    (memory $large 2)
+    (data (memory $large) (i32.const 70000) "\00\05")
+
+    ;; in bounds for $large only, and reads a zero divisor there
+    (func $load_large (param $x i32) (result i32)
+        local.get $x
+        i32.const 70000
+        i32.load8_u $large
+        i32.div_u
+    )
+
+    ;; the same address is out of bounds in $small
+    (func $load_small (result i32)
+        i32.const 70000
+        i32.load8_u $small
+    )
+
+    ;; growing one memory leaves the other's size unchanged
+    (func $grow (result i32)
+        i32.const 1
+        memory.grow $large
+        drop
+        memory.size $small
+    )
+)
@@ -0,0 +1,23 @@
+(module
+    (memory 1)
+
+    ;; the last word of the page is in bounds
+    (func $last_word (result i32)
+        i32.const 65532
+        i32.load
+    )
+
+    ;; the static offset pushes the access past the end
+    (func $offset (param $addr i32) (result i32)
+        local.get $addr
+        i32.const 0xff
+        i32.and
+        i32.load offset=65530
+    )
+
+    ;; base + offset wraps around 32 bits, which traps rather than reading address 0
+    (func $wrap (result i32)
+        i32.const -4
+        i32.load offset=8
+    )
+)
@@ -0,0 +1,27 @@
+(module
+    ;; the divisor can't be zero, but INT_MIN / -1 still overflows
+    (func $overflowdiv (param $divisor i32) (param $dividend i32) (result i32)
+        local.get $divisor
+        i32.eqz
+        if (result i32)
+            i32.const 0
+        else
+            local.get $dividend
+            local.get $divisor
+            i32.div_s
+        end
+    )
+
+    ;; the remainder of INT_MIN / -1 is 0 and doesn't trap
+    (func $overflowrem (param $divisor i64) (param $dividend i64) (result i64)
+        local.get $divisor
+        i64.eqz
+        if (result i64)
+            i64.const 0
+        else
+            local.get $dividend
+            local.get $divisor
+            i64.rem_s
+        end
+    )
+)
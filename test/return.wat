@@ -0,0 +1,30 @@
+(module
+    (func $clamp (param $x i32) (result i32)
+        block $done
+            local.get $x
+            i32.const 10
+            i32.lt_u
+            br_if $done
+            i32.const 10
+            return
+        end
+        local.get $x
+    )
+
+    (func $early (param $x i32) (result i32)
+        local.get $x
+        local.get $x
+        br_if 0
+        drop
+        i32.const 0
+    )
+
+    (func $caller (param $x i32) (result i32)
+        i32.const 100
+        local.get $x
+        call $clamp
+        i32.const 5
+        i32.sub
+        i32.div_u
+    )
+)
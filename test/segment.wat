@@ -0,0 +1,11 @@
+(module
+    (memory 1)
+    (data (i32.const 0) "ok")
+    ;; ends one byte past the single page, so instantiation fails
+    (data (i32.const 65535) "\01\02")
+
+    (func $read (result i32)
+        i32.const 0
+        i32.load8_u
+    )
+)
@@ -0,0 +1,33 @@
+(module
+    (memory 1)
+
+    ;; 64-bit store past the end of the single page when $addr is large
+    (func $store64 (param $addr i32)
+        local.get $addr
+        i64.const 1
+        i64.store
+    )
+
+    ;; the mask keeps the store in bounds
+    (func $store_masked (param $addr i32)
+        local.get $addr
+        i32.const 0xff
+        i32.and
+        f32.const 1.5
+        f32.store
+    )
+
+    (func $fill (param $len i32)
+        i32.const 65000
+        i32.const 0
+        local.get $len
+        memory.fill
+    )
+
+    (func $copy (param $src i32)
+        i32.const 0
+        local.get $src
+        i32.const 16
+        memory.copy
+    )
+)
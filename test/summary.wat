@@ -0,0 +1,75 @@
+(module
+    (memory 1)
+    ;; straight-line loop with a closed-form trip count, summarized instead of unrolled
+    (func $count (param $n i32) (result i32)
+        (local $i i32)
+        (local $sum i32)
+        loop
+            local.get $sum
+            i32.const 3
+            i32.add
+            local.set $sum
+            local.get $i
+            i32.const 1
+            i32.add
+            local.tee $i
+            local.get $n
+            i32.lt_u
+            br_if 0
+        end
+        ;; divides by zero when the loop runs 1000 times
+        i32.const 1
+        local.get $sum
+        i32.const 3000
+        i32.sub
+        i32.div_u
+    )
+    ;; loop with a branch and a store, widened once it gets too hot
+    (func $widen (param $n i32) (result i32)
+        (local $i i32)
+        loop
+            local.get $i
+            i32.const 7
+            i32.and
+            if
+                local.get $i
+                local.get $i
+                i32.store8
+            end
+            local.get $i
+            i32.const 1
+            i32.add
+            local.tee $i
+            local.get $n
+            i32.lt_u
+            br_if 0
+        end
+        ;; divides by zero when the loop runs 100 times
+        i32.const 1
+        local.get $i
+        i32.const 100
+        i32.sub
+        i32.div_u
+    )
+    ;; loop that writes a float local back unchanged, which is not an induction
+    (func $carry (param $n i32) (param $x f32) (result i32)
+        (local $i i32)
+        loop
+            local.get $x
+            local.set $x
+            local.get $i
+            i32.const 1
+            i32.add
+            local.tee $i
+            local.get $n
+            i32.lt_u
+            br_if 0
+        end
+        ;; divides by zero when the loop runs 10 times
+        i32.const 1
+        local.get $i
+        i32.const 10
+        i32.sub
+        i32.div_u
+    )
+)
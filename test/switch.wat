@@ -0,0 +1,35 @@
+(module
+    (func $switch (param $x i32) (param $y i32) (result i32)
+        block $default
+            block $two
+                block $one
+                    block $zero
+                        local.get $x
+                        br_table $zero $one $one $two $default
+                    end
+                    i32.const 1
+                    return
+                end
+                i32.const 100
+                local.get $y
+                i32.div_u
+                return
+            end
+            i32.const 3
+            return
+        end
+        i32.const 4
+    )
+
+    (func $concrete (result i32)
+        block $b
+            block $a
+                i32.const 7
+                br_table $a $b
+            end
+            i32.const 1
+            return
+        end
+        i32.const 2
+    )
+)
@@ -0,0 +1,20 @@
+(module
+    ;; the loop is the last instruction, so a branch to it must restart it rather than return
+    (func $tail (param $n i32)
+        loop $again
+            local.get $n
+            i32.const 1
+            i32.sub
+            local.tee $n
+            i32.const 3
+            i32.ne
+            br_if $again
+            i32.const 1
+            local.get $n
+            i32.const 3
+            i32.sub
+            i32.div_u
+            drop
+        end
+    )
+)
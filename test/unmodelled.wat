@@ -0,0 +1,39 @@
+(module
+    (import "env" "get_ref" (func $get_ref (result externref)))
+
+    (func $splat (param $x i32) (result i32)
+        local.get $x
+        i32.const 0
+        i32.lt_s
+        if (result i32)
+            i32.const 1
+        else
+            local.get $x
+            i32x4.splat
+            i32x4.extract_lane 0
+        end
+    )
+
+    (func $unused_ref (param $r externref) (param $x i32) (result i32)
+        local.get $x
+        i32.const 1
+        i32.add
+    )
+
+    (func $read_ref (param $r externref) (result i32)
+        local.get $r
+        ref.is_null
+    )
+
+    (func $vector_local (result i32)
+        (local $v v128)
+        v128.const i32x4 1 2 3 4
+        local.set $v
+        i32.const 0
+    )
+
+    (func $imported_ref (result i32)
+        call $get_ref
+        ref.is_null
+    )
+)
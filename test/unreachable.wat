@@ -0,0 +1,28 @@
+(module
+    ;; assertion that fails when $x is 42
+    (func $assert (param $x i32) (result i32)
+        local.get $x
+        i32.const 42
+        i32.eq
+        if
+            unreachable
+        end
+        local.get $x
+    )
+
+    ;; the guard makes the trap infeasible
+    (func $guarded (param $x i32) (result i32)
+        local.get $x
+        i32.const 10
+        i32.lt_u
+        if
+            local.get $x
+            i32.const 20
+            i32.gt_u
+            if
+                unreachable
+            end
+        end
+        local.get $x
+    )
+)
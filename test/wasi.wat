@@ -0,0 +1,58 @@
+(module
+    (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "args_sizes_get" (func $args_sizes_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+    (memory 1)
+    (data (i32.const 16) "\40\00\00\00\04\00\00\00")
+    (data (i32.const 32) "\50\00\00\00\03\00\00\00")
+    (data (i32.const 80) "hi\0a")
+
+    (func $read_divide (result i32)
+        i32.const 0
+        i32.const 16
+        i32.const 1
+        i32.const 8
+        call $fd_read
+        drop
+        i32.const 100
+        i32.const 64
+        i32.load8_u
+        i32.div_u
+    )
+
+    (func $write (result i32)
+        i32.const 1
+        i32.const 32
+        i32.const 1
+        i32.const 8
+        call $fd_write
+        drop
+        i32.const 8
+        i32.load
+    )
+
+    (func $argc (result i32)
+        i32.const 0
+        i32.const 4
+        call $args_sizes_get
+        drop
+        i32.const 100
+        i32.const 0
+        i32.load
+        i32.const 1
+        i32.sub
+        i32.div_u
+    )
+
+    (func $exit (param $x i32) (result i32)
+        local.get $x
+        if
+            i32.const 1
+            call $proc_exit
+        end
+        i32.const 1
+        local.get $x
+        i32.div_u
+    )
+)
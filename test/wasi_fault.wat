@@ -0,0 +1,28 @@
+(module
+    (import "wasi_snapshot_preview1" "random_get" (func $random_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+    (memory 1)
+    (data (i32.const 16) "\fc\ff\00\00\08\00\00\00")
+
+    (func $random_past_end (result i32)
+        i32.const 100
+        i32.const 65530
+        i32.const 16
+        call $random_get
+        i32.const 21
+        i32.sub
+        i32.div_u
+    )
+
+    (func $read_past_end (result i32)
+        i32.const 100
+        i32.const 0
+        i32.const 16
+        i32.const 1
+        i32.const 8
+        call $fd_read
+        i32.const 21
+        i32.sub
+        i32.div_u
+    )
+)
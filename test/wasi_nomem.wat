@@ -0,0 +1,13 @@
+(module
+    (import "wasi_snapshot_preview1" "random_get" (func $random_get (param i32 i32) (result i32)))
+
+    (func $random (result i32)
+        i32.const 100
+        i32.const 0
+        i32.const 4
+        call $random_get
+        i32.const 52
+        i32.sub
+        i32.div_u
+    )
+)